@@ -5,7 +5,8 @@ cargo build --release
 sudo cp ./target/release/amdgpu-settings /usr/local/bin/amdgpu-settings
 sudo chmod +x /usr/local/bin/amdgpu-settings
 
-sudo cp ./src/amdgpu-settings.resume /usr/lib/systemd/system-sleep/amdgpu-settings.resume
-sudo chmod +x /usr/lib/systemd/system-sleep/amdgpu-settings.resume
+# systemd-sleep calls the binary directly through this symlink (see `amdgpu-settings hook sleep`)
+sudo rm -f /usr/lib/systemd/system-sleep/amdgpu-settings.resume
+sudo ln -sf /usr/local/bin/amdgpu-settings /usr/lib/systemd/system-sleep/amdgpu-settings
 
 sudo cp ./src/amdgpu-settings.service /etc/systemd/system/amdgpu-settings.service
@@ -0,0 +1,450 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Threshold alerts on the live sensors (ALERT in the profile). A firing alert is logged, runs
+ * ALERT_COMMAND and can switch to ALERT_FALLBACK_PROFILE. FALLBACK_PROFILE is the thermal safety
+ * layer: applied when a FALLBACK_TRIGGER fires and undone once the card has cooled down.
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::{doctor, log, sensors, state, throttle, DeviceConfig};
+use crate::sensors::Sample;
+
+const DEFAULT_FALLBACK_HYSTERESIS: u32 = 5; // Celsius
+const DEFAULT_FALLBACK_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sensor {
+    Edge, // Celsius
+    Junction,
+    Mem,
+    VrGfx, // gpu_metrics only
+    VrSoc,
+    VrMem,
+    Fan, // RPM
+    Pwm, // Percent
+    Power, // W
+    Sclk, // Mhz
+    Mclk,
+    GpuBusy, // Percent
+    Vddgfx, // mV
+}
+
+impl Sensor {
+    pub fn is_temperature(self) -> bool {
+        matches!(self, Sensor::Edge | Sensor::Junction | Sensor::Mem | Sensor::VrGfx | Sensor::VrSoc | Sensor::VrMem)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Threshold(Sensor, Comparison, f64),
+    Throttle(String), // Limiter name or THERMAL, POWER, CURRENT
+}
+
+// "junction > 100C for 10s", "fan == 0RPM while junction > 70C", "throttle has THERMAL"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub text: String,
+    pub conditions: Vec<Condition>, // All have to hold
+    pub duration: Duration, // How long before the alert fires
+}
+
+fn parse_sensor(name: &str) -> Result<Sensor, String> {
+    match name {
+        "edge" => Ok(Sensor::Edge),
+        "junction" => Ok(Sensor::Junction),
+        "mem" => Ok(Sensor::Mem),
+        "vr_gfx" => Ok(Sensor::VrGfx),
+        "vr_soc" => Ok(Sensor::VrSoc),
+        "vr_mem" => Ok(Sensor::VrMem),
+        "fan" => Ok(Sensor::Fan),
+        "pwm" => Ok(Sensor::Pwm),
+        "power" => Ok(Sensor::Power),
+        "sclk" => Ok(Sensor::Sclk),
+        "mclk" => Ok(Sensor::Mclk),
+        "gpu_busy" => Ok(Sensor::GpuBusy),
+        "vddgfx" => Ok(Sensor::Vddgfx),
+        _ => Err(format!("Unknown sensor {} (edge, junction, mem, vr_gfx, vr_soc, vr_mem, fan, pwm, power, sclk, mclk, gpu_busy or vddgfx)", name)),
+    }
+}
+
+fn parse_comparison(op: &str) -> Result<Comparison, String> {
+    match op {
+        ">" => Ok(Comparison::Greater),
+        ">=" => Ok(Comparison::GreaterEqual),
+        "<" => Ok(Comparison::Less),
+        "<=" => Ok(Comparison::LessEqual),
+        "==" => Ok(Comparison::Equal),
+        "!=" => Ok(Comparison::NotEqual),
+        _ => Err(format!("Unknown comparison {} (>, >=, <, <=, == or !=)", op)),
+    }
+}
+
+// "junction > 100C" or "throttle has TEMP_HOTSPOT"
+pub fn parse_condition(text: &str) -> Result<Condition, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words[..] {
+        ["throttle" | "throttle_status", "has", limiter] => Ok(Condition::Throttle(limiter.to_uppercase())),
+        [sensor, op, value] => {
+            // Units are only for readability (C or °C, RPM, %, W, Mhz, mV)
+            let number = value.trim_end_matches(|c: char| c.is_alphabetic() || c == '%' || c == '°');
+            let value = number.parse().map_err(|_| format!("Invalid value {}", value))?;
+            Ok(Condition::Threshold(parse_sensor(sensor)?, parse_comparison(op)?, value))
+        },
+        _ => Err(format!("Invalid condition \"{}\" (expected \"[sensor] [comparison] [value]\" or \"throttle has [limiter]\")", text)),
+    }
+}
+
+pub fn parse_alert(line: &str) -> Result<Alert, String> {
+    let text = line.trim();
+    let (rest, duration) = match text.rsplit_once(" for ") {
+        Some((rest, duration)) => (rest, log::parse_duration(duration.trim())?),
+        None => (text, Duration::ZERO),
+    };
+    let conditions = rest.split(" while ").map(parse_condition).collect::<Result<Vec<Condition>, String>>()?;
+    Ok(Alert { text: text.to_owned(), conditions, duration })
+}
+
+fn sensor_value(sensor: Sensor, sample: &Sample) -> Option<f64> {
+    match sensor {
+        Sensor::Edge => sample.temp_edge,
+        Sensor::Junction => sample.temp_junction,
+        Sensor::Mem => sample.temp_mem,
+        Sensor::VrGfx => sample.metrics.as_ref().and_then(|m| m.temperature_vrgfx).map(f64::from),
+        Sensor::VrSoc => sample.metrics.as_ref().and_then(|m| m.temperature_vrsoc).map(f64::from),
+        Sensor::VrMem => sample.metrics.as_ref().and_then(|m| m.temperature_vrmem).map(f64::from),
+        Sensor::Fan => sample.fan_rpm.map(|v| v as f64),
+        Sensor::Pwm => sample.fan_pwm.map(|v| v as f64),
+        // power1_average is missing on RDNA 3 or newer (power1_input instead)
+        Sensor::Power => sample.power_average.or(sample.power_input),
+        Sensor::Sclk => sample.sclk.map(|v| v as f64),
+        Sensor::Mclk => sample.mclk.map(|v| v as f64),
+        Sensor::GpuBusy => sample.gpu_busy.map(f64::from),
+        Sensor::Vddgfx => sample.vddgfx.map(|v| v as f64),
+    }
+}
+
+fn limiter_matches(limiter: &str, wanted: &str) -> bool {
+    match wanted {
+        "THERMAL" => limiter.starts_with("TEMP_") || limiter.starts_with("VRHOT") || limiter.starts_with("PROCHOT"),
+        "POWER" => limiter.starts_with("PPT") || limiter.starts_with("SPL") || limiter.starts_with("FPPT") || limiter.starts_with("SPPT"),
+        "CURRENT" => limiter.starts_with("TDC_") || limiter.starts_with("EDC_") || limiter == "APCC",
+        _ => limiter == wanted,
+    }
+}
+
+// A sensor the card doesn't have never holds
+pub fn holds(condition: &Condition, sample: &Sample) -> bool {
+    match condition {
+        Condition::Threshold(sensor, comparison, threshold) => {
+            let Some(value) = sensor_value(*sensor, sample) else {
+                return false;
+            };
+            match comparison {
+                Comparison::Greater => value > *threshold,
+                Comparison::GreaterEqual => value >= *threshold,
+                Comparison::Less => value < *threshold,
+                Comparison::LessEqual => value <= *threshold,
+                Comparison::Equal => value == *threshold,
+                Comparison::NotEqual => value != *threshold,
+            }
+        },
+        Condition::Throttle(wanted) => sample.metrics.as_ref()
+            .and_then(throttle::active_limiters)
+            .is_some_and(|limiters| limiters.iter().any(|(limiter, _)| limiter_matches(limiter, wanted))),
+    }
+}
+
+// Whether a condition is past its threshold by the hysteresis (e.g. "junction > 100C" with 5 of
+// hysteresis clears below 95C)
+pub fn clears(condition: &Condition, sample: &Sample, hysteresis: f64) -> bool {
+    match condition {
+        Condition::Threshold(sensor, comparison, threshold) => {
+            let Some(value) = sensor_value(*sensor, sample) else {
+                return true;
+            };
+            match comparison {
+                Comparison::Greater | Comparison::GreaterEqual => value < *threshold - hysteresis,
+                Comparison::Less | Comparison::LessEqual => value > *threshold + hysteresis,
+                Comparison::Equal | Comparison::NotEqual => !holds(condition, sample),
+            }
+        },
+        Condition::Throttle(_) => !holds(condition, sample),
+    }
+}
+
+// Under systemd stdout goes to the journal, where "<4>" marks the line as a warning
+pub fn log_warning(message: &str) {
+    if std::env::var_os("JOURNAL_STREAM").is_some() {
+        println!("<4>{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+fn run_command(command: &str, alert: &Alert, profile: &str, device: &str) {
+    let result = Command::new("sh").arg("-c").arg(command)
+        .env("AMDGPU_ALERT", &alert.text)
+        .env("AMDGPU_PROFILE", profile)
+        .env("AMDGPU_DEVICE", device)
+        .status();
+    match result {
+        Ok(status) if !status.success() => log_warning(&format!("ALERT_COMMAND exited with {}", status)),
+        Ok(_) => {},
+        Err(e) => log_warning(&format!("Failed to run ALERT_COMMAND: {}", e)),
+    }
+}
+
+// Applies a profile without stopping the watcher if it can't be applied
+fn switch_profile(profile: &str) -> bool {
+    let applied = matches!(doctor::catch_unwind_quietly(|| crate::set_profile(profile, None)), Ok(Ok(())));
+    if !applied {
+        log_warning(&format!("Failed to apply profile {}", profile));
+    }
+    applied
+}
+
+// Since when each alert's conditions hold. Returns the alerts that held long enough this sample.
+fn check_alerts<'a>(alerts: &'a [Alert], since: &mut [Option<Instant>], sample: &Sample) -> Vec<(usize, &'a Alert)> {
+    let mut firing = Vec::new();
+    for (i, alert) in alerts.iter().enumerate() {
+        if !alert.conditions.iter().all(|condition| holds(condition, sample)) {
+            since[i] = None;
+            continue;
+        }
+        let start = *since[i].get_or_insert_with(Instant::now);
+        if start.elapsed() >= alert.duration {
+            firing.push((i, alert));
+        }
+    }
+    firing
+}
+
+// FALLBACK_PROFILE state: whether it is applied, and since when every trigger cleared
+#[derive(Default)]
+struct Fallback {
+    applied: bool,
+    cleared_since: Option<Instant>,
+}
+
+fn check_fallback(profile: &str, config: &DeviceConfig, device: &str, fallback: &mut Fallback, since: &mut [Option<Instant>], sample: &Sample) {
+    let Some(fallback_profile) = &config.fallback_profile else {
+        return;
+    };
+    if !fallback.applied {
+        if let Some((_, trigger)) = check_alerts(&config.fallback_triggers, since, sample).first() {
+            log_warning(&format!("FALLBACK on {}: {}. Switching to profile {}", device, trigger.text, fallback_profile));
+            fallback.applied = switch_profile(fallback_profile);
+            fallback.cleared_since = None;
+            since.fill(None);
+        }
+        return;
+    }
+
+    let hysteresis = config.fallback_hysteresis.unwrap_or(DEFAULT_FALLBACK_HYSTERESIS) as f64;
+    let cleared = config.fallback_triggers.iter()
+        .all(|trigger| trigger.conditions.iter().any(|condition| clears(condition, sample, hysteresis)));
+    if !cleared {
+        fallback.cleared_since = None;
+        return;
+    }
+    let start = *fallback.cleared_since.get_or_insert_with(Instant::now);
+    if start.elapsed() >= config.fallback_cooldown.unwrap_or(DEFAULT_FALLBACK_COOLDOWN) {
+        log_warning(&format!("Cooled down on {}. Restoring profile {}", device, profile));
+        fallback.applied = !switch_profile(profile);
+        fallback.cleared_since = None;
+    }
+}
+
+// Switching to a profile of another card would never cool this one down
+fn check_same_device(key: &str, fallback: &str, device: &str) {
+    let config = crate::parse_profile(&(crate::CONFIG_PROFILE_PATH.to_owned() + fallback));
+    let fallback_device = state::device_key(&config.home_path);
+    if fallback_device != device {
+        panic!("{} {} is for {}, but the watched profile is for {}", key, fallback, fallback_device, device);
+    }
+}
+
+// Checks the profile's alerts and fallback triggers every interval until stopped
+pub fn run(profile: &str, config: DeviceConfig, interval: Duration) {
+    if config.alerts.is_empty() && config.fallback_profile.is_none() {
+        panic!("Profile {} has no ALERT or FALLBACK_PROFILE", profile);
+    }
+    if config.fallback_profile.is_some() && config.fallback_triggers.is_empty() {
+        panic!("Profile {} has a FALLBACK_PROFILE but no FALLBACK_TRIGGER", profile);
+    }
+    let device = state::device_key(&config.home_path);
+    if let Some(fallback) = &config.fallback_profile {
+        check_same_device("FALLBACK_PROFILE", fallback, &device);
+    }
+    if let Some(fallback) = &config.alert_fallback_profile {
+        check_same_device("ALERT_FALLBACK_PROFILE", fallback, &device);
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .expect("Failed to set signal handler");
+
+    // An alert fires again only after its conditions cleared
+    let mut since: Vec<Option<Instant>> = vec![None; config.alerts.len()];
+    let mut fired = vec![false; config.alerts.len()];
+    let mut trigger_since: Vec<Option<Instant>> = vec![None; config.fallback_triggers.len()];
+    let mut fallback = Fallback::default();
+    println!("Watching {} alerts and {} fallback triggers of profile {} on {}",
+        config.alerts.len(), config.fallback_triggers.len(), profile, device);
+    while running.load(Ordering::SeqCst) {
+        let sample = sensors::read_sample(&config.home_path, Some(&config.hwmon_path));
+        let firing = check_alerts(&config.alerts, &mut since, &sample);
+        for (i, alert) in config.alerts.iter().enumerate() {
+            if fired[i] && since[i].is_none() {
+                println!("Alert cleared: {}", alert.text);
+                fired[i] = false;
+            }
+        }
+        for (i, alert) in firing {
+            if fired[i] {
+                continue;
+            }
+            fired[i] = true;
+
+            let active_profile = state::active_profile(&device).unwrap_or_default();
+            log_warning(&format!("ALERT on {} (profile {}): {}", device, active_profile, alert.text));
+            if let Some(command) = &config.alert_command {
+                run_command(command, alert, &active_profile, &device);
+            }
+            if let Some(fallback) = &config.alert_fallback_profile {
+                if active_profile != *fallback {
+                    log_warning(&format!("Switching {} to fallback profile {}", device, fallback));
+                    switch_profile(fallback);
+                }
+            }
+        }
+
+        check_fallback(profile, &config, &device, &mut fallback, &mut trigger_since, &sample);
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu_metrics::GpuMetrics;
+
+    fn throttling(indep_throttle_status: u64) -> Sample {
+        let metrics = GpuMetrics { indep_throttle_status: Some(indep_throttle_status), ..Default::default() };
+        Sample { metrics: Some(metrics), ..Default::default() }
+    }
+
+    #[test]
+    fn parse_alert_with_for_and_while() {
+        let alert = parse_alert("fan == 0RPM while junction > 70°C for 10s").unwrap();
+        assert_eq!(alert.conditions, vec![
+            Condition::Threshold(Sensor::Fan, Comparison::Equal, 0.0),
+            Condition::Threshold(Sensor::Junction, Comparison::Greater, 70.0),
+        ]);
+        assert_eq!(alert.duration, Duration::from_secs(10));
+        assert_eq!(alert.text, "fan == 0RPM while junction > 70°C for 10s");
+
+        let alert = parse_alert("throttle_status has thermal").unwrap();
+        assert_eq!(alert.conditions, vec![Condition::Throttle(String::from("THERMAL"))]);
+        assert_eq!(alert.duration, Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_condition_units() {
+        assert_eq!(parse_condition("junction >= 100C"), Ok(Condition::Threshold(Sensor::Junction, Comparison::GreaterEqual, 100.0)));
+        assert_eq!(parse_condition("pwm < 30%"), Ok(Condition::Threshold(Sensor::Pwm, Comparison::Less, 30.0)));
+        assert_eq!(parse_condition("power > 250.5W"), Ok(Condition::Threshold(Sensor::Power, Comparison::Greater, 250.5)));
+        assert_eq!(parse_condition("vddgfx != 1100mV"), Ok(Condition::Threshold(Sensor::Vddgfx, Comparison::NotEqual, 1100.0)));
+        assert!(parse_condition("hotspot > 100C").is_err());
+        assert!(parse_condition("junction => 100C").is_err());
+        assert!(parse_condition("junction > hot").is_err());
+        assert!(parse_alert("junction > 100C for 10 days").is_err());
+    }
+
+    #[test]
+    fn missing_sensor_never_holds() {
+        let condition = parse_condition("junction > 100C").unwrap();
+        let sample = Sample::default();
+        assert!(!holds(&condition, &sample));
+        assert!(clears(&condition, &sample, 5.0));
+        assert!(!holds(&parse_condition("throttle has THERMAL").unwrap(), &sample));
+    }
+
+    #[test]
+    fn clears_by_hysteresis() {
+        let condition = parse_condition("junction > 100C").unwrap();
+        let sample = |temp| Sample { temp_junction: Some(temp), ..Default::default() };
+        assert!(holds(&condition, &sample(101.0)));
+        assert!(!holds(&condition, &sample(98.0)));
+        assert!(!clears(&condition, &sample(98.0), 5.0));
+        assert!(clears(&condition, &sample(94.0), 5.0));
+
+        let condition = parse_condition("edge < 10C").unwrap();
+        assert!(!clears(&condition, &Sample { temp_edge: Some(12.0), ..Default::default() }, 5.0));
+        assert!(clears(&condition, &Sample { temp_edge: Some(16.0), ..Default::default() }, 5.0));
+    }
+
+    #[test]
+    fn throttle_groups() {
+        let thermal = parse_condition("throttle has THERMAL").unwrap();
+        let power = parse_condition("throttle has POWER").unwrap();
+        let current = parse_condition("throttle has CURRENT").unwrap();
+        let hotspot = parse_condition("throttle has TEMP_HOTSPOT").unwrap();
+
+        // TEMP_HOTSPOT
+        let sample = throttling(1 << 36);
+        assert!(holds(&thermal, &sample) && holds(&hotspot, &sample));
+        assert!(!holds(&power, &sample) && !holds(&current, &sample));
+        // PPT0
+        let sample = throttling(1 << 0);
+        assert!(holds(&power, &sample));
+        assert!(!holds(&thermal, &sample) && !holds(&current, &sample));
+        // TDC_GFX and APCC
+        for bit in [16, 23] {
+            let sample = throttling(1 << bit);
+            assert!(holds(&current, &sample));
+            assert!(!holds(&thermal, &sample) && !holds(&power, &sample));
+        }
+        // VRHOT0 and PROCHOT_GFX are thermal too
+        assert!(holds(&thermal, &throttling(1 << 44)));
+        assert!(holds(&thermal, &throttling(1 << 47)));
+        assert!(!holds(&thermal, &throttling(0)));
+    }
+
+    #[test]
+    fn alert_fires_again_after_clearing() {
+        let alerts = vec![parse_alert("junction > 100C").unwrap(), parse_alert("edge > 90C for 1h").unwrap()];
+        let mut since = vec![None; alerts.len()];
+        let hot = Sample { temp_junction: Some(105.0), temp_edge: Some(95.0), ..Default::default() };
+        let cool = Sample { temp_junction: Some(60.0), temp_edge: Some(50.0), ..Default::default() };
+
+        let firing: Vec<usize> = check_alerts(&alerts, &mut since, &hot).iter().map(|(i, _)| *i).collect();
+        assert_eq!(firing, vec![0]); // The second one has to hold for an hour first
+        assert!(since.iter().all(Option::is_some));
+
+        assert!(check_alerts(&alerts, &mut since, &cool).is_empty());
+        assert!(since.iter().all(Option::is_none));
+
+        let firing: Vec<usize> = check_alerts(&alerts, &mut since, &hot).iter().map(|(i, _)| *i).collect();
+        assert_eq!(firing, vec![0]);
+    }
+}
@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Detection of what the card (and kernel) supports, so profiles for another generation are refused
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::{dpm, od, DeviceConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Generation {
+    Gcn, // Vega, Polaris (per state OD_SCLK/OD_MCLK with voltages)
+    Rdna1,
+    Rdna2,
+    Rdna3,
+    Rdna4,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for Generation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Generation::Gcn => "GCN (Vega/Polaris)",
+            Generation::Rdna1 => "RDNA 1",
+            Generation::Rdna2 => "RDNA 2",
+            Generation::Rdna3 => "RDNA 3",
+            Generation::Rdna4 => "RDNA 4",
+            Generation::Unknown => "Unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    pub gc_version: Option<(u32, u32, u32)>, // Graphics core IP version from ip_discovery
+    pub generation: Generation,
+    pub od_sections: Vec<String>, // e.g. OD_SCLK, OD_MCLK, OD_VDDGFX_OFFSET (pp_od_clk_voltage)
+    pub fan_ctrl: Vec<String>, // Files in gpu_od/fan_ctrl
+    pub power_profile: bool,
+    pub dpm_files: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn has_od(&self, section: &str) -> bool {
+        self.od_sections.iter().any(|s| s == section)
+    }
+
+    pub fn has_fan_ctrl(&self, file: &str) -> bool {
+        self.fan_ctrl.iter().any(|f| f == file)
+    }
+}
+
+fn read_ip_value(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// ip_discovery/die/0/GC/0/{major,minor,revision} (Navi or newer)
+fn read_gc_version(home_path: &Path) -> Option<(u32, u32, u32)> {
+    let gc_path = home_path.join("ip_discovery/die/0/GC/0");
+    Some((
+        read_ip_value(&gc_path.join("major"))?,
+        read_ip_value(&gc_path.join("minor"))?,
+        read_ip_value(&gc_path.join("revision"))?,
+    ))
+}
+
+fn classify(gc_version: Option<(u32, u32, u32)>, od_table: Option<&od::OdTable>) -> Generation {
+    match gc_version {
+        Some((12, _, _)) => return Generation::Rdna4,
+        Some((11, _, _)) => return Generation::Rdna3,
+        Some((10, 3, _)) => return Generation::Rdna2,
+        Some((10, _, _)) => return Generation::Rdna1,
+        Some((9, _, _)) => return Generation::Gcn,
+        _ => {},
+    }
+    // Older kernels/cards without ip_discovery: guess from the OD sections
+    let Some(od_table) = od_table else {
+        return Generation::Unknown;
+    };
+    if od_table.section("OD_SCLK_OFFSET").is_some() {
+        Generation::Rdna4
+    } else if od_table.section("OD_VDDC_CURVE").is_some() {
+        Generation::Rdna1
+    } else if od_table.section("OD_SCLK").is_some_and(|s| s.entries.len() > 2) {
+        Generation::Gcn
+    } else {
+        Generation::Unknown
+    }
+}
+
+pub fn probe(home_path: &Path) -> Capabilities {
+    let od_table = od::read_od_table(home_path);
+    let gc_version = read_gc_version(home_path);
+    let mut fan_ctrl: Vec<String> = fs::read_dir(home_path.join("gpu_od/fan_ctrl"))
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    fan_ctrl.sort();
+    let dpm_files = dpm::dpm_paths(home_path).iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+
+    Capabilities {
+        gc_version,
+        generation: classify(gc_version, od_table.as_ref()),
+        od_sections: od_table.as_ref()
+            .map(|table| table.sections.iter().map(|section| section.name.clone()).collect())
+            .unwrap_or_default(),
+        fan_ctrl,
+        power_profile: home_path.join("pp_power_profile_mode").exists(),
+        dpm_files,
+    }
+}
+
+// Every profile setting the card doesn't support (FAN_ZERO_RPM_* and FAN_MINIMUM_PWM depend on
+// the kernel version and are skipped when applying instead)
+pub fn check(config: &DeviceConfig, capabilities: &Capabilities) -> Result<(), String> {
+    let generation = capabilities.generation;
+    let mut unsupported: Vec<String> = Vec::new();
+    let od_settings = [
+        ("OD_SCLK_OFFSET", config.od_sclk_offset.is_some(), "RDNA 4 only, use OD_SCLK on older cards"),
+        ("OD_SCLK", !config.od_sclk.is_empty(), "use OD_SCLK_OFFSET on RDNA 4"),
+        ("OD_MCLK", !config.od_mclk.is_empty(), ""),
+        ("OD_VDDGFX_OFFSET", config.od_vddgfx_offset.is_some(), "RDNA 3 or newer"),
+        ("OD_VDDC_CURVE", !config.od_vddc_curve.is_empty(), "RDNA 1 and some RDNA 2 only"),
+    ];
+    for (section, set, hint) in od_settings {
+        if set && !capabilities.has_od(section) {
+            unsupported.push(format!("{}: pp_od_clk_voltage has no {} section{}", section, section,
+                if hint.is_empty() { String::new() } else { format!(" ({})", hint) }));
+        }
+    }
+
+    let fan_ctrl_settings = [
+        ("OD_ACOUSTIC_LIMIT", config.acoustic_limit_rpm_threshold.is_some(), "acoustic_limit_rpm_threshold"),
+        ("OD_ACOUSTIC_TARGET", config.acoustic_target_rpm_threshold.is_some(), "acoustic_target_rpm_threshold"),
+        ("FAN_TARGET_TEMPERATURE", config.fan_target_temp.is_some(), "fan_target_temperature"),
+        ("FAN_CURVE", !config.fan_curve.is_empty(), "fan_curve"),
+    ];
+    for (key, set, file) in fan_ctrl_settings {
+        if set && !capabilities.has_fan_ctrl(file) {
+            unsupported.push(format!("{}: gpu_od/fan_ctrl/{} is missing (RDNA 3 or newer, use SOFTWARE_FAN_CURVE on older cards)", key, file));
+        }
+    }
+
+    let power_profile_set = config.power_profile_index.is_some() || config.power_profile.is_some()
+        || !config.power_profile_custom.is_empty();
+    if power_profile_set && !capabilities.power_profile {
+        unsupported.push(String::from("POWER_PROFILE*: pp_power_profile_mode is missing"));
+    }
+    for (key, _) in &config.dpm_masks {
+        let file = dpm::dpm_file(key).unwrap();
+        if !capabilities.dpm_files.iter().any(|f| f == file) {
+            unsupported.push(format!("{}: {} is missing", key, file));
+        }
+    }
+
+    if unsupported.is_empty() {
+        return Ok(());
+    }
+    Err(format!("Profile has settings the card ({}) doesn't support:\n  {}", generation, unsupported.join("\n  ")))
+}
+
+pub fn print_capabilities(capabilities: &Capabilities) {
+    match capabilities.gc_version {
+        Some((major, minor, revision)) => {
+            println!("GENERATION: {} (GC {}.{}.{})", capabilities.generation, major, minor, revision);
+        },
+        None => println!("GENERATION: {}", capabilities.generation),
+    }
+    println!("OD: {}", capabilities.od_sections.join(" "));
+    println!("FAN_CTRL: {}", capabilities.fan_ctrl.join(" "));
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Preflight checks of the kernel and the amdgpu.ppfeaturemask boot parameter
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use crate::capabilities;
+
+const PPFEATUREMASK_PATH: &str = "/sys/module/amdgpu/parameters/ppfeaturemask";
+const CMDLINE_PATH: &str = "/proc/cmdline";
+const OSRELEASE_PATH: &str = "/proc/sys/kernel/osrelease"; // Same as `uname -r`
+const CMDLINE_PARAMETER: &str = "amdgpu.ppfeaturemask";
+
+// Cleared while a panic is caught and handled by the caller (e.g. the watcher switching profiles)
+static REPORT_PANICS: AtomicBool = AtomicBool::new(true);
+
+// Bits of amdgpu.ppfeaturemask (enum PP_FEATURE_MASK in amd_shared.h) that the settings rely on
+const FEATURE_BITS: [(u32, &str); 3] = [
+    (0x4000, "PP_OVERDRIVE_MASK (pp_od_clk_voltage, gpu_od/fan_ctrl)"),
+    (0x1, "PP_SCLK_DPM_MASK (pp_dpm_sclk, DPM_SCLK)"),
+    (0x2, "PP_MCLK_DPM_MASK (pp_dpm_mclk, DPM_MCLK)"),
+];
+
+const MINIMUM_KERNEL: (u32, u32) = (6, 10);
+
+// (major, minor, what the kernel adds). Only for information, since whether they matter depends on
+// the card and the profile.
+const KERNEL_FEATURES: [(u32, u32, &str); 3] = [
+    (6, 7, "gpu_od/fan_ctrl (FAN_CURVE, OD_ACOUSTIC_*, FAN_TARGET_TEMPERATURE, FAN_MINIMUM_PWM) on RDNA 3"),
+    (6, 12, "RDNA 4 overdrive (6.14 is recommended)"),
+    (6, 13, "FAN_ZERO_RPM_ENABLE and FAN_ZERO_RPM_STOP_TEMPERATURE"),
+];
+
+// "0xfff7bfff" or "4294950911"
+fn parse_mask(value: &str) -> Option<u32> {
+    let value = value.trim();
+    match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+// "6.13.2-arch1-1" -> (6, 13)
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut numbers = release.trim().split(|c: char| !c.is_ascii_digit());
+    Some((numbers.next()?.parse().ok()?, numbers.next()?.parse().ok()?))
+}
+
+// The last amdgpu.ppfeaturemask= wins
+fn cmdline_mask(cmdline: &str) -> Option<&str> {
+    cmdline.split_whitespace()
+        .filter_map(|parameter| parameter.strip_prefix(CMDLINE_PARAMETER)?.strip_prefix('='))
+        .next_back()
+}
+
+// Kernel and ppfeaturemask problems with how to fix them. Returns false if a problem was found.
+fn check_system(lines: &mut Vec<String>) -> bool {
+    let mut ok = true;
+
+    // Kernel version
+    let release = fs::read_to_string(OSRELEASE_PATH).unwrap_or_default();
+    match parse_kernel_version(&release) {
+        Some((major, minor)) => {
+            lines.push(format!("Kernel: {}", release.trim()));
+            if (major, minor) < MINIMUM_KERNEL {
+                ok = false;
+                lines.push(format!("  Too old: amdgpu-settings needs Linux {}.{} or newer", MINIMUM_KERNEL.0, MINIMUM_KERNEL.1));
+            }
+            for (feature_major, feature_minor, feature) in KERNEL_FEATURES {
+                if (major, minor) < (feature_major, feature_minor) {
+                    lines.push(format!("  Not available (needs Linux {}.{}): {}", feature_major, feature_minor, feature));
+                }
+            }
+        },
+        None => lines.push(format!("Kernel: unknown (can't read {})", OSRELEASE_PATH)),
+    }
+
+    // amdgpu.ppfeaturemask
+    let cmdline = fs::read_to_string(CMDLINE_PATH).unwrap_or_default();
+    let cmdline_value = cmdline_mask(&cmdline);
+    match fs::read_to_string(PPFEATUREMASK_PATH).ok().as_deref().and_then(parse_mask) {
+        Some(mask) => {
+            lines.push(format!("ppfeaturemask: {:#010x} (kernel command line: {})", mask,
+                cmdline_value.map(|v| format!("{}={}", CMDLINE_PARAMETER, v)).unwrap_or(String::from("not set"))));
+            let missing: Vec<&(u32, &str)> = FEATURE_BITS.iter().filter(|(bit, _)| mask & bit == 0).collect();
+            for (bit, name) in &missing {
+                lines.push(format!("  Missing bit {:#x}: {}", bit, name));
+            }
+            if !missing.is_empty() {
+                ok = false;
+                let fixed = missing.iter().fold(mask, |mask, (bit, _)| mask | bit);
+                match cmdline_value {
+                    Some(value) => lines.push(format!("  Fix: replace {}={} with {}={:#010x} on the kernel command line",
+                        CMDLINE_PARAMETER, value, CMDLINE_PARAMETER, fixed)),
+                    None => lines.push(format!("  Fix: add {}={:#010x} to the kernel command line", CMDLINE_PARAMETER, fixed)),
+                }
+                lines.push(String::from("  (e.g. GRUB_CMDLINE_LINUX_DEFAULT in /etc/default/grub, then regenerate the grub config and reboot)"));
+            }
+        },
+        None => {
+            ok = false;
+            lines.push(format!("ppfeaturemask: can't read {} (is the amdgpu module loaded?)", PPFEATUREMASK_PATH));
+        },
+    }
+    ok
+}
+
+// Prints what is missing and how to fix it. Returns false if a problem was found.
+pub fn report(home_path: Option<&Path>) -> bool {
+    println!("---------- amdgpu-settings doctor ----------");
+    let mut lines = Vec::new();
+    let mut ok = check_system(&mut lines);
+    for line in &lines {
+        println!("{}", line);
+    }
+
+    // Card
+    if let Some(home_path) = home_path {
+        if !home_path.join("pp_od_clk_voltage").exists() {
+            ok = false;
+            println!("Card: {} has no pp_od_clk_voltage (overdrive is disabled or unsupported)", home_path.display());
+        } else {
+            capabilities::print_capabilities(&capabilities::probe(home_path));
+        }
+    }
+
+    if ok {
+        println!("No problems found");
+    }
+    ok
+}
+
+// Same as report without a card, but on stderr so it never ends up in the output of e.g. info --format json
+pub fn report_to_stderr() {
+    eprintln!("---------- amdgpu-settings doctor ----------");
+    let mut lines = Vec::new();
+    if check_system(&mut lines) {
+        lines.push(String::from("No problems found"));
+    }
+    for line in &lines {
+        eprintln!("{}", line);
+    }
+}
+
+// Explain the likely cause when a command panics (e.g. "Can't access pp_od_clk_voltage file")
+pub fn report_on_panic() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if REPORT_PANICS.load(Ordering::SeqCst) {
+            eprintln!();
+            report_to_stderr();
+        }
+    }));
+}
+
+// catch_unwind for panics the caller handles itself, so they don't print the report
+pub fn catch_unwind_quietly<T>(f: impl FnOnce() -> T) -> thread::Result<T> {
+    REPORT_PANICS.store(false, Ordering::SeqCst);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    REPORT_PANICS.store(true, Ordering::SeqCst);
+    result
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * DPM level masking through pp_dpm_* (requires the manual performance level)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use glob::glob;
+use serde::Serialize;
+
+// Profile key -> sysfs file
+pub const DPM_FILES: [(&str, &str); 5] = [
+    ("DPM_SCLK", "pp_dpm_sclk"),
+    ("DPM_MCLK", "pp_dpm_mclk"),
+    ("DPM_FCLK", "pp_dpm_fclk"),
+    ("DPM_SOCCLK", "pp_dpm_socclk"),
+    ("DPM_PCIE", "pp_dpm_pcie"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DpmLevel {
+    pub index: u8,
+    pub value: String, // e.g. "800Mhz" or "16.0GT/s, x16 619Mhz"
+    pub active: bool,
+}
+
+pub fn dpm_file(key: &str) -> Option<&'static str> {
+    DPM_FILES.iter().find(|(k, _)| *k == key).map(|(_, file)| *file)
+}
+
+// 0: 500Mhz
+// 1: 800Mhz *
+// 2: 2500Mhz
+pub fn parse_dpm_levels(content: &str) -> Vec<DpmLevel> {
+    content.lines().filter_map(|line| {
+        let (index, value) = line.split_once(':')?;
+        let value = value.trim();
+        let active = value.ends_with('*');
+        Some(DpmLevel {
+            index: index.trim().parse().ok()?,
+            value: value.trim_end_matches('*').trim().to_owned(),
+            active,
+        })
+    }).collect()
+}
+
+pub fn read_dpm_levels(home_path: &Path, file: &str) -> Option<Vec<DpmLevel>> {
+    let content = fs::read_to_string(home_path.join(file)).ok()?;
+    Some(parse_dpm_levels(&content))
+}
+
+// Every pp_dpm_* file of the card (sorted by name)
+pub fn dpm_paths(home_path: &Path) -> Vec<PathBuf> {
+    let dpm_pattern = home_path.join("pp_dpm_*");
+    let mut paths: Vec<PathBuf> = glob(dpm_pattern.to_str().unwrap())
+        .expect("Failed to detect pp_dpm files")
+        .flatten()
+        .collect();
+    paths.sort();
+    paths
+}
+
+pub fn validate_dpm_mask(key: &str, mask: &[u8], levels: &[DpmLevel]) -> Result<(), String> {
+    if mask.is_empty() {
+        return Err(format!("{} needs at least one level", key));
+    }
+    for index in mask {
+        if !levels.iter().any(|level| level.index == *index) {
+            let available: Vec<String> = levels.iter().map(|level| level.index.to_string()).collect();
+            return Err(format!("{} level {} does not exist (available: {})", key, index, available.join(" ")));
+        }
+    }
+    Ok(())
+}
+
+pub fn print_dpm_levels(file: &str, levels: &[DpmLevel]) {
+    println!("{}:", file.to_uppercase());
+    for level in levels {
+        println!("{}: {}{}", level.index, level.value, if level.active { " *" } else { "" });
+    }
+}
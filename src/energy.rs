@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Energy accounting: integrate the power draw over time, attribute it to the active profile and
+ * keep daily totals per device
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::{log, sensors, state, DeviceConfig};
+
+// NOTE: Unlike the active profile state this has to survive a reboot
+const LEDGER_PATH: &str = "/var/lib/amdgpu-settings/energy";
+const LEDGER_HEADER: &str = "day,profile,joules,seconds,peak_watts";
+// gpu_metrics energy_accumulator unit
+const ENERGY_UNIT_J: f64 = 15.259e-6;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+const NO_PROFILE: &str = "-";
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    joules: f64,
+    seconds: f64,
+    peak_watts: f64,
+}
+
+impl Usage {
+    fn add(&mut self, joules: f64, seconds: f64, watts: f64) {
+        self.joules += joules;
+        self.seconds += seconds;
+        self.peak_watts = self.peak_watts.max(watts);
+    }
+
+    fn merge(&mut self, other: &Usage) {
+        self.add(other.joules, other.seconds, other.peak_watts);
+    }
+
+    fn kwh(&self) -> f64 {
+        self.joules / 3.6e6
+    }
+
+    fn average_watts(&self) -> f64 {
+        if self.seconds > 0.0 { self.joules / self.seconds } else { 0.0 }
+    }
+}
+
+// (day, profile) -> usage
+type Ledger = BTreeMap<(String, String), Usage>;
+
+fn ledger_file(device: &str) -> PathBuf {
+    PathBuf::from(LEDGER_PATH).join(device)
+}
+
+// Profile names are quoted by log::csv_field
+fn unescape_profile(profile: &str) -> String {
+    match profile.strip_prefix('"').and_then(|profile| profile.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => profile.to_owned(),
+    }
+}
+
+fn parse_ledger(content: &str) -> Ledger {
+    let mut ledger = Ledger::new();
+    for line in content.lines().skip(1) {
+        // The profile is whatever is between the day and the last three columns
+        let Some((day, rest)) = line.split_once(',') else {
+            continue;
+        };
+        let fields: Vec<&str> = rest.rsplitn(4, ',').collect();
+        let [peak_watts, seconds, joules, profile] = fields[..] else {
+            continue;
+        };
+        let (Ok(joules), Ok(seconds), Ok(peak_watts)) = (joules.parse(), seconds.parse(), peak_watts.parse()) else {
+            continue;
+        };
+        ledger.insert((day.to_owned(), unescape_profile(profile)), Usage { joules, seconds, peak_watts });
+    }
+    ledger
+}
+
+fn read_ledger(device: &str) -> Ledger {
+    fs::read_to_string(ledger_file(device)).map(|content| parse_ledger(&content)).unwrap_or_default()
+}
+
+fn write_ledger(device: &str, ledger: &Ledger) {
+    let mut content = format!("{}\n", LEDGER_HEADER);
+    for ((day, profile), usage) in ledger {
+        content.push_str(&format!("{},{},{:.3},{:.3},{:.3}\n", day, log::csv_field(profile), usage.joules, usage.seconds, usage.peak_watts));
+    }
+    let tmp_path = PathBuf::from(format!("{}.tmp", ledger_file(device).display()));
+    let result = fs::create_dir_all(LEDGER_PATH)
+        .and_then(|_| fs::write(&tmp_path, content))
+        .and_then(|_| fs::rename(&tmp_path, ledger_file(device)));
+    if let Err(e) = result {
+        println!("Unable to save the energy ledger of {}: {}", device, e);
+    }
+}
+
+// YYYY-MM-DD (UTC) of a unix timestamp
+fn day(timestamp: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn today() -> String {
+    day(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+}
+
+fn print_usage_header() {
+    println!("{:<12} {:<20} {:>10} {:>8} {:>8} {:>8}", "DAY", "PROFILE", "KWH", "AVG W", "PEAK W", "HOURS");
+}
+
+fn print_usage(day: &str, profile: &str, usage: &Usage) {
+    println!("{:<12} {:<20} {:>10.4} {:>8.1} {:>8.1} {:>8.2}",
+        day, profile, usage.kwh(), usage.average_watts(), usage.peak_watts, usage.seconds / 3600.0);
+}
+
+// A second sampler of the same device would overwrite the other's totals with its own
+fn lock_ledger(device: &str) -> File {
+    let path = PathBuf::from(LEDGER_PATH).join(format!("{}.lock", device));
+    let file = fs::create_dir_all(LEDGER_PATH).and_then(|_| File::create(&path))
+        .unwrap_or_else(|e| panic!("Unable to create {}: {}", path.display(), e));
+    if file.try_lock().is_err() {
+        panic!("Energy of {} is already being recorded by another amdgpu-settings energy", device);
+    }
+    file
+}
+
+// Samples until stopped and adds the energy to the ledger of the device
+pub fn run(config: &DeviceConfig, interval: Duration) {
+    let device = state::device_key(&config.home_path);
+    let _lock = lock_ledger(&device);
+    let mut ledger = read_ledger(&device);
+    let mut session: BTreeMap<String, Usage> = BTreeMap::new();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .expect("Failed to set signal handler");
+
+    let sample = sensors::read_sample(&config.home_path, Some(&config.hwmon_path));
+    let mut last_energy = sample.metrics.and_then(|metrics| metrics.energy_accumulator);
+    let mut last_sample = Instant::now();
+    let mut last_flush = Instant::now();
+    while running.load(Ordering::SeqCst) {
+        thread::sleep(interval);
+        let sample = sensors::read_sample(&config.home_path, Some(&config.hwmon_path));
+        let seconds = last_sample.elapsed().as_secs_f64();
+        last_sample = Instant::now();
+
+        // power1_average is missing on RDNA 3 or newer (power1_input instead)
+        let watts = sample.power_average.or(sample.power_input).unwrap_or(0.0);
+        let energy = sample.metrics.and_then(|metrics| metrics.energy_accumulator);
+        // The accumulator is more accurate than power * time but resets with the SMU. The firmware
+        // updates it less often than we sample, so an unchanged value is 0 J (the next update has it).
+        let joules = match (last_energy, energy) {
+            (Some(last), Some(energy)) if energy >= last => (energy - last) as f64 * ENERGY_UNIT_J,
+            _ => watts * seconds,
+        };
+        last_energy = energy;
+
+        let profile = state::active_profile(&device).unwrap_or(NO_PROFILE.to_owned());
+        ledger.entry((today(), profile.clone())).or_default().add(joules, seconds, watts);
+        session.entry(profile).or_default().add(joules, seconds, watts);
+
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            write_ledger(&device, &ledger);
+            last_flush = Instant::now();
+        }
+    }
+    write_ledger(&device, &ledger);
+
+    println!("Session energy of {}:", device);
+    print_usage_header();
+    for (profile, usage) in &session {
+        print_usage(&today(), profile, usage);
+    }
+}
+
+// Daily energy per profile of every recorded device (last days only if given)
+pub fn print_stats(days: Option<usize>) {
+    let Ok(entries) = fs::read_dir(LEDGER_PATH) else {
+        println!("No energy recorded yet (run amdgpu-settings energy)");
+        return;
+    };
+    let mut devices: Vec<String> = entries.flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|device| !device.ends_with(".tmp") && !device.ends_with(".lock"))
+        .collect();
+    devices.sort();
+
+    for device in devices {
+        let ledger = read_ledger(&device);
+        let mut recorded_days: Vec<&String> = ledger.keys().map(|(day, _)| day).collect();
+        recorded_days.dedup();
+        let first_day = days.and_then(|days| recorded_days.len().checked_sub(days)).map(|skip| recorded_days[skip].clone());
+
+        println!("---------- Energy of {} ----------", device);
+        print_usage_header();
+        let mut totals: BTreeMap<&str, Usage> = BTreeMap::new();
+        for ((day, profile), usage) in &ledger {
+            if first_day.as_ref().is_some_and(|first_day| day < first_day) {
+                continue;
+            }
+            print_usage(day, profile, usage);
+            totals.entry(profile).or_default().merge(usage);
+        }
+        println!();
+        for (profile, usage) in &totals {
+            print_usage("TOTAL", profile, usage);
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_names_with_commas() {
+        let content = format!("{}\n2026-10-17,{},3600.000,60.000,80.000\n2026-10-17,{},1.000,2.000,3.000\n2026-10-18,quiet,1.5,1,1\n",
+            LEDGER_HEADER, log::csv_field("uv,quiet"), log::csv_field("say \"hi\""));
+        let ledger = parse_ledger(&content);
+        assert_eq!(ledger.len(), 3);
+        let usage = ledger[&(String::from("2026-10-17"), String::from("uv,quiet"))];
+        assert_eq!((usage.joules, usage.seconds, usage.peak_watts), (3600.0, 60.0, 80.0));
+        assert!(ledger.contains_key(&(String::from("2026-10-17"), String::from("say \"hi\""))));
+        assert!(ledger.contains_key(&(String::from("2026-10-18"), String::from("quiet"))));
+        assert!(parse_ledger(&format!("{}\n2026-10-18,broken\n", LEDGER_HEADER)).is_empty());
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Prometheus exporter of the sensors of every amdgpu card (http /metrics or a node_exporter
+ * textfile)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use glob::glob;
+use crate::{find_hwmon_path, sensors, state, throttle};
+
+// A client that stops sending or reading must not block the single threaded server
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct ExporterOptions {
+    pub listen: String,
+    pub textfile: Option<PathBuf>,
+    pub interval: Option<Duration>, // Rewrite the textfile this often (otherwise write it once)
+}
+
+struct Card {
+    name: String, // card1
+    home_path: PathBuf,
+    hwmon_path: Option<PathBuf>,
+}
+
+// Every drm card driven by amdgpu (skips connectors like card1-DP-1 and other drivers)
+fn amdgpu_cards() -> Vec<Card> {
+    let mut cards: Vec<Card> = glob("/sys/class/drm/card*").expect("Failed to detect drm card path")
+        .flatten()
+        .filter_map(|card_path| {
+            let name = card_path.file_name()?.to_string_lossy().into_owned();
+            if !name.trim_start_matches("card").chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let driver = fs::read_link(card_path.join("device/driver")).ok()?;
+            if driver.file_name()? != "amdgpu" {
+                return None;
+            }
+            Some(Card { name, home_path: card_path.join("device"), hwmon_path: find_hwmon_path(&card_path) })
+        })
+        .collect();
+    cards.sort_by_key(|card| card.name.trim_start_matches("card").parse::<u32>().unwrap_or(0));
+    cards
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// Metric families in the order they were first seen, each printed with one HELP and TYPE line
+#[derive(Default)]
+struct Registry {
+    families: Vec<(&'static str, &'static str, Vec<String>)>, // (name, help, samples)
+}
+
+impl Registry {
+    fn gauge<T: std::fmt::Display>(&mut self, name: &'static str, help: &'static str, labels: &[(&str, &str)], value: Option<T>) {
+        let Some(value) = value else {
+            return;
+        };
+        let labels: Vec<String> = labels.iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+            .collect();
+        let sample = format!("{}{{{}}} {}", name, labels.join(","), value);
+        match self.families.iter_mut().find(|(family, _, _)| *family == name) {
+            Some((_, _, samples)) => samples.push(sample),
+            None => self.families.push((name, help, vec![sample])),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for (name, help, samples) in &self.families {
+            text.push_str(&format!("# HELP {} {}\n# TYPE {} gauge\n", name, help, name));
+            for sample in samples {
+                text.push_str(sample);
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+fn collect_card(registry: &mut Registry, card: &Card) {
+    let pci_slot = state::device_key(&card.home_path);
+    let unique_id = fs::read_to_string(card.home_path.join("unique_id"))
+        .map(|id| id.trim().to_owned())
+        .unwrap_or_default();
+    let device: [(&str, &str); 3] = [("card", &card.name), ("pci_slot", &pci_slot), ("unique_id", &unique_id)];
+    let with = |label: &'static str, value: &'static str| {
+        let mut labels = device.to_vec();
+        labels.push((label, value));
+        labels
+    };
+
+    let profile = state::active_profile(&pci_slot).unwrap_or_default();
+    registry.gauge("amdgpu_profile_info", "Profile last applied by amdgpu-settings (empty if none)",
+        &[device.as_slice(), &[("profile", profile.as_str())]].concat(), Some(1));
+
+    let sample = sensors::read_sample(&card.home_path, card.hwmon_path.as_deref());
+    for (sensor, value) in [("edge", sample.temp_edge), ("junction", sample.temp_junction), ("mem", sample.temp_mem)] {
+        registry.gauge("amdgpu_temperature_celsius", "Temperature sensors", &with("sensor", sensor), value);
+    }
+    for (kind, value) in [("average", sample.power_average), ("input", sample.power_input)] {
+        registry.gauge("amdgpu_power_watts", "Power draw", &with("type", kind), value);
+    }
+    registry.gauge("amdgpu_power_cap_watts", "Power cap (POWER_CAP)", &device, sample.power_cap);
+    for (clock, value) in [("sclk", sample.sclk), ("mclk", sample.mclk)] {
+        registry.gauge("amdgpu_clock_mhz", "Current clocks", &with("clock", clock), value);
+    }
+    registry.gauge("amdgpu_fan_rpm", "Fan speed", &device, sample.fan_rpm);
+    registry.gauge("amdgpu_fan_pwm_percent", "Fan duty cycle", &device, sample.fan_pwm);
+    for (block, value) in [("gpu", sample.gpu_busy), ("mem", sample.mem_busy)] {
+        registry.gauge("amdgpu_busy_percent", "Busy percent", &with("block", block), value);
+    }
+    registry.gauge("amdgpu_vram_used_bytes", "VRAM in use", &device, sample.vram_used);
+    registry.gauge("amdgpu_vram_total_bytes", "VRAM size", &device, sample.vram_total);
+    for (rail, value) in [("vddgfx", sample.vddgfx), ("vddnb", sample.vddnb)] {
+        registry.gauge("amdgpu_voltage_millivolts", "Voltages", &with("rail", rail), value);
+    }
+
+    let Some(metrics) = &sample.metrics else {
+        return;
+    };
+    registry.gauge("amdgpu_throttle_status", "Raw gpu_metrics throttle_status bits", &device, metrics.throttle_status);
+    // One series per limiter the card reports so alerts can match on the limiter label (the 64 bit
+    // indep_throttle_status doesn't fit in a float sample)
+    if let Some(active) = throttle::active_limiters(metrics) {
+        for limiter in throttle::limiters(metrics) {
+            let value = active.iter().any(|(name, _)| *name == limiter) as u8;
+            registry.gauge("amdgpu_throttle_active", "Limiter holding the clocks back (1 when active)", &with("limiter", limiter), Some(value));
+        }
+    }
+}
+
+fn collect() -> String {
+    let mut registry = Registry::default();
+    for card in amdgpu_cards() {
+        collect_card(&mut registry, &card);
+    }
+    registry.render()
+}
+
+// Write next to the file and rename so node_exporter never reads half a file
+fn write_textfile(path: &Path) {
+    let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&tmp_path, collect()).expect("Failed to write the textfile");
+    fs::rename(&tmp_path, path).expect("Failed to write the textfile");
+}
+
+fn respond(mut stream: TcpStream) {
+    if stream.set_read_timeout(Some(CLIENT_TIMEOUT)).and(stream.set_write_timeout(Some(CLIENT_TIMEOUT))).is_err() {
+        return;
+    }
+    let mut request_line = String::new();
+    if BufReader::new(&stream).read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = collect();
+            format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body)
+        },
+        _ => String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    };
+    let _ = stream.write_all(response.as_bytes());
+}
+
+pub fn run(options: ExporterOptions) {
+    if let Some(path) = options.textfile {
+        loop {
+            write_textfile(&path);
+            match options.interval {
+                Some(interval) => thread::sleep(interval),
+                None => return,
+            }
+        }
+    }
+
+    let listener = TcpListener::bind(&options.listen)
+        .unwrap_or_else(|e| panic!("Unable to listen on {}: {}", options.listen, e));
+    println!("Serving metrics on http://{}/metrics", options.listen);
+    for stream in listener.incoming().flatten() {
+        respond(stream);
+    }
+}
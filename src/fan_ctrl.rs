@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Parsing of the gpu_od/fan_ctrl files (SMU 13 or newer)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FanCurvePoint {
+    pub index: u8,
+    pub temp: u32, // Celsius (hotspot)
+    pub pwm: u32, // Percent
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FanCurve {
+    pub points: Vec<FanCurvePoint>,
+    #[serde(serialize_with = "serialize_range")]
+    pub temp_range: Option<(u32, u32)>,
+    #[serde(serialize_with = "serialize_range")]
+    pub pwm_range: Option<(u32, u32)>,
+}
+
+// (min, max) -> {"min": .., "max": ..} in info --format json/yaml
+fn serialize_range<S: Serializer>(range: &Option<(u32, u32)>, serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Range {
+        min: u32,
+        max: u32,
+    }
+    range.map(|(min, max)| Range { min, max }).serialize(serializer)
+}
+
+fn parse_number(value: &str) -> Option<u32> {
+    value.trim_end_matches(|c: char| !c.is_ascii_digit()).parse().ok()
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct FanCtrlValue {
+    pub value: Option<u32>,
+    #[serde(serialize_with = "serialize_range")]
+    pub range: Option<(u32, u32)>,
+}
+
+// Single value fan_ctrl files, e.g. fan_minimum_pwm:
+// FAN_MINIMUM_PWM:
+// 15
+// OD_RANGE:
+// MINIMUM_PWM: 15 100
+pub fn parse_fan_ctrl_value(content: &str) -> FanCtrlValue {
+    let mut value = FanCtrlValue::default();
+    let mut in_range = false;
+    for line in content.lines().map(str::trim) {
+        if line == "OD_RANGE:" {
+            in_range = true;
+        } else if in_range {
+            value.range = value.range.or(parse_range(line));
+        } else if value.value.is_none() {
+            value.value = parse_number(line);
+        }
+    }
+    value
+}
+
+pub fn read_fan_ctrl_value(home_path: &Path, name: &str) -> Option<FanCtrlValue> {
+    let content = fs::read_to_string(home_path.join("gpu_od/fan_ctrl").join(name)).ok()?;
+    Some(parse_fan_ctrl_value(&content))
+}
+
+pub fn validate_fan_ctrl_value(name: &str, value: u32, current: &FanCtrlValue) -> Result<(), String> {
+    match current.range {
+        Some((min, max)) if value < min || value > max => {
+            Err(format!("{} {} is out of range ({} - {})", name, value, min, max))
+        },
+        _ => Ok(()),
+    }
+}
+
+// "0: 40C 20%" (same format in the profile and in the fan_curve file)
+pub fn parse_fan_curve_point(line: &str) -> Option<FanCurvePoint> {
+    let (index, values) = line.split_once(':')?;
+    let mut values = values.split_whitespace();
+    Some(FanCurvePoint {
+        index: index.trim().parse().ok()?,
+        temp: parse_number(values.next()?)?,
+        pwm: parse_number(values.next()?)?,
+    })
+}
+
+fn parse_range(line: &str) -> Option<(u32, u32)> {
+    let (_, values) = line.split_once(':')?;
+    let mut values = values.split_whitespace();
+    Some((parse_number(values.next()?)?, parse_number(values.next()?)?))
+}
+
+// OD_FAN_CURVE:
+// 0: 0C 0%
+// ...
+// OD_RANGE:
+// FAN_CURVE(hotspot temp): 25C 100C
+// FAN_CURVE(fan speed): 15% 100%
+pub fn parse_fan_curve(content: &str) -> FanCurve {
+    let mut curve = FanCurve::default();
+    let mut in_range = false;
+    for line in content.lines().map(str::trim) {
+        if line == "OD_RANGE:" {
+            in_range = true;
+        } else if in_range && line.contains("temp") {
+            curve.temp_range = parse_range(line);
+        } else if in_range && line.contains("speed") {
+            curve.pwm_range = parse_range(line);
+        } else if let Some(point) = parse_fan_curve_point(line) {
+            curve.points.push(point);
+        }
+    }
+    curve
+}
+
+pub fn read_fan_curve(home_path: &Path) -> Option<FanCurve> {
+    let content = fs::read_to_string(home_path.join("gpu_od/fan_ctrl/fan_curve")).ok()?;
+    Some(parse_fan_curve(&content))
+}
+
+pub fn validate_fan_curve(points: &[FanCurvePoint], curve: &FanCurve) -> Result<(), String> {
+    for point in points {
+        if usize::from(point.index) >= curve.points.len() {
+            return Err(format!("FAN_CURVE point {} does not exist (card has {} points)",
+                point.index, curve.points.len()));
+        }
+        if let Some((min, max)) = curve.temp_range {
+            if point.temp < min || point.temp > max {
+                return Err(format!("FAN_CURVE point {} temperature {}C is out of range ({}C - {}C)",
+                    point.index, point.temp, min, max));
+            }
+        }
+        if let Some((min, max)) = curve.pwm_range {
+            if point.pwm < min || point.pwm > max {
+                return Err(format!("FAN_CURVE point {} fan speed {}% is out of range ({}% - {}%)",
+                    point.index, point.pwm, min, max));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn print_fan_curve(curve: &FanCurve) {
+    println!("FAN_CURVE:");
+    println!("{:>5} {:>8} {:>6}", "POINT", "HOTSPOT", "FAN");
+    for point in &curve.points {
+        println!("{:>5} {:>7}C {:>5}%", point.index, point.temp, point.pwm);
+    }
+    if let (Some(temp), Some(pwm)) = (curve.temp_range, curve.pwm_range) {
+        println!("OD_RANGE: {}C - {}C, {}% - {}%", temp.0, temp.1, pwm.0, pwm.1);
+    }
+}
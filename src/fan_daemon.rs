@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Software fan curve through hwmon pwm1 (for cards without gpu_od/fan_ctrl, e.g. RDNA 1/2, Vega, Polaris)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use crate::DeviceConfig;
+
+const PWM_ENABLE_MANUAL: &str = "1";
+const PWM_ENABLE_AUTO: &str = "2";
+const PWM_MAX: u32 = 255;
+const DEFAULT_SENSOR: &str = "junction";
+const DEFAULT_HYSTERESIS: u32 = 3; // Celsius
+const DEFAULT_SPIN_UP_RATE: u32 = 100; // Percent per step (no limit)
+const DEFAULT_SPIN_DOWN_RATE: u32 = 5; // Percent per step
+const DEFAULT_INTERVAL: u64 = 1000; // ms
+
+// (temperature in Celsius, fan speed in percent)
+pub type SoftwareFanCurvePoint = (u32, u32);
+
+// "40C 20%"
+pub fn parse_software_fan_curve_point(line: &str) -> Option<SoftwareFanCurvePoint> {
+    let mut values = line.split_whitespace();
+    let temp = values.next()?.trim_end_matches('C').parse().ok()?;
+    let pwm = values.next()?.trim_end_matches('%').parse().ok()?;
+    Some((temp, pwm))
+}
+
+// Linear interpolation between the curve points (clamped to the first/last point)
+fn curve_pwm(curve: &[SoftwareFanCurvePoint], temp: u32) -> u32 {
+    let (first_temp, first_pwm) = curve[0];
+    if temp <= first_temp {
+        return first_pwm;
+    }
+    for pair in curve.windows(2) {
+        let ((t0, p0), (t1, p1)) = (pair[0], pair[1]);
+        if temp <= t1 {
+            if t1 == t0 {
+                return p1;
+            }
+            let pwm = p0 as i64 + (p1 as i64 - p0 as i64) * (temp - t0) as i64 / (t1 - t0) as i64;
+            return pwm as u32;
+        }
+    }
+    curve[curve.len() - 1].1
+}
+
+fn temp_input(sensor: &str) -> &'static str {
+    match sensor {
+        "edge" => "temp1_input",
+        "junction" => "temp2_input",
+        "mem" => "temp3_input",
+        _ => panic!("Invalid SOFTWARE_FAN_SENSOR (edge, junction or mem)"),
+    }
+}
+
+fn read_value(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Hand the fan back to the driver when the daemon exits (also when unwinding from a panic)
+struct AutoFanGuard {
+    hwmon_path: PathBuf,
+}
+
+impl Drop for AutoFanGuard {
+    fn drop(&mut self) {
+        match fs::write(self.hwmon_path.join("pwm1_enable"), PWM_ENABLE_AUTO) {
+            Ok(_) => println!("Restored automatic fan control"),
+            Err(e) => println!("Failed to restore pwm1_enable to automatic: {}", e),
+        }
+    }
+}
+
+pub fn run(name: &str, config: DeviceConfig) {
+    let mut curve = config.software_fan_curve.clone();
+    if curve.is_empty() {
+        panic!("Profile {} has no SOFTWARE_FAN_CURVE", name);
+    }
+    curve.sort();
+    let mut sensor = config.software_fan_sensor.as_deref().unwrap_or(DEFAULT_SENSOR);
+    // Check the sensor before taking over the fan (older cards like Polaris only have temp1_input)
+    if !config.hwmon_path.join(temp_input(sensor)).exists() && sensor != "edge" {
+        println!("Card has no {} temperature ({}), using edge instead", sensor, temp_input(sensor));
+        sensor = "edge";
+    }
+    let temp_path = config.hwmon_path.join(temp_input(sensor));
+    if !temp_path.exists() {
+        panic!("Can't access {} file", temp_path.display());
+    }
+    let pwm_path = config.hwmon_path.join("pwm1");
+    let pwm_enable_path = config.hwmon_path.join("pwm1_enable");
+    let hysteresis = config.software_fan_hysteresis.unwrap_or(DEFAULT_HYSTERESIS);
+    let spin_up_rate = config.software_fan_spin_up_rate.unwrap_or(DEFAULT_SPIN_UP_RATE);
+    let spin_down_rate = config.software_fan_spin_down_rate.unwrap_or(DEFAULT_SPIN_DOWN_RATE);
+    let interval = Duration::from_millis(config.software_fan_interval.unwrap_or(DEFAULT_INTERVAL));
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .expect("Failed to set signal handler");
+
+    fs::write(&pwm_enable_path, PWM_ENABLE_MANUAL).expect("Can't set pwm1_enable to manual");
+    let _guard = AutoFanGuard { hwmon_path: config.hwmon_path.clone() };
+    println!("Controlling fan of {} with the {} temperature (Ctrl-C to stop)...", name.to_uppercase(), sensor);
+
+    let mut pwm = read_value(&pwm_path).expect("Can't read pwm1") * 100 / PWM_MAX;
+    let mut written_pwm: Option<u32> = None;
+    let mut reference_temp: Option<u32> = None;
+    while running.load(Ordering::SeqCst) {
+        let temp = read_value(&temp_path).expect("Can't read temperature") / 1000;
+
+        // Follow rising temperatures right away, falling ones only past the hysteresis
+        reference_temp = match reference_temp {
+            Some(reference) if temp < reference && temp + hysteresis > reference => Some(reference),
+            _ => Some(temp),
+        };
+        let target = curve_pwm(&curve, reference_temp.unwrap()).min(100);
+        let next_pwm = if target > pwm {
+            pwm + (target - pwm).min(spin_up_rate)
+        } else {
+            pwm - (pwm - target).min(spin_down_rate)
+        };
+
+        // The driver switches back to automatic on its own (e.g. after resume)
+        if fs::read_to_string(&pwm_enable_path).map(|v| v.trim() != PWM_ENABLE_MANUAL).unwrap_or(true) {
+            fs::write(&pwm_enable_path, PWM_ENABLE_MANUAL).expect("Can't set pwm1_enable to manual");
+            written_pwm = None;
+        }
+        if written_pwm != Some(next_pwm) {
+            fs::write(&pwm_path, (next_pwm * PWM_MAX / 100).to_string()).expect("Failed to write pwm1");
+            written_pwm = Some(next_pwm);
+        }
+        pwm = next_pwm;
+        thread::sleep(interval);
+    }
+}
@@ -0,0 +1,513 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Decoding of the binary gpu_metrics file (struct gpu_metrics_v* in kgd_pp_interface.h)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+// The driver reports unsupported fields as all ones
+const UNSUPPORTED_U16: u16 = u16::MAX;
+const UNSUPPORTED_U32: u32 = u32::MAX;
+const UNSUPPORTED_U64: u64 = u64::MAX;
+
+// Every layout decoded into one struct (fields the revision doesn't have are None)
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GpuMetrics {
+    pub format_revision: u8,
+    pub content_revision: u8,
+    pub system_clock_counter: Option<u64>, // ns
+    pub firmware_timestamp: Option<u64>, // 10ns
+    // Temperatures (Celsius)
+    pub temperature_edge: Option<u16>,
+    pub temperature_hotspot: Option<u16>,
+    pub temperature_mem: Option<u16>,
+    pub temperature_vrgfx: Option<u16>,
+    pub temperature_vrsoc: Option<u16>,
+    pub temperature_vrmem: Option<u16>,
+    pub temperature_gfx: Option<u16>, // APU
+    pub temperature_soc: Option<u16>, // APU
+    pub temperature_core: Vec<u16>, // APU CPU cores
+    // Activity (percent)
+    pub average_gfx_activity: Option<u16>,
+    pub average_umc_activity: Option<u16>,
+    pub average_mm_activity: Option<u16>,
+    // Power (mW) and energy (15.259uJ units)
+    pub average_socket_power: Option<u32>,
+    pub average_gfx_power: Option<u32>, // APU
+    pub energy_accumulator: Option<u64>,
+    // Clocks (Mhz)
+    pub average_gfxclk_frequency: Option<u16>,
+    pub average_socclk_frequency: Option<u16>,
+    pub average_uclk_frequency: Option<u16>,
+    pub average_fclk_frequency: Option<u16>,
+    pub current_gfxclk: Option<u16>,
+    pub current_socclk: Option<u16>,
+    pub current_uclk: Option<u16>,
+    pub current_fclk: Option<u16>,
+    pub current_coreclk: Vec<u16>, // APU CPU cores
+    // Voltages (mV)
+    pub voltage_soc: Option<u16>,
+    pub voltage_gfx: Option<u16>,
+    pub voltage_mem: Option<u16>,
+    // Throttle status (ASIC dependent, and ASIC independent bits)
+    pub throttle_status: Option<u32>,
+    pub indep_throttle_status: Option<u64>,
+    // Fan
+    pub current_fan_speed: Option<u16>, // RPM
+    pub fan_pwm: Option<u16>, // APU
+    // PCIe link (lanes, 0.1 GT/s)
+    pub pcie_link_width: Option<u16>,
+    pub pcie_link_speed: Option<u16>,
+}
+
+// Reads the fields in declaration order with the C struct alignment (every field is aligned to its size)
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn read<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.offset = self.offset.next_multiple_of(N);
+        let bytes = self.data.get(self.offset..self.offset + N)?.try_into().ok()?;
+        self.offset += N;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.read::<1>().map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.read().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.read().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.read().map(u64::from_le_bytes)
+    }
+
+    fn u16s(&mut self, count: usize) -> Option<Vec<u16>> {
+        (0..count).map(|_| self.u16()).collect()
+    }
+
+    // Fields that aren't decoded (N bytes each)
+    fn skip<const N: usize>(&mut self, count: usize) -> Option<()> {
+        for _ in 0..count {
+            self.read::<N>()?;
+        }
+        Some(())
+    }
+}
+
+fn valid16(value: u16) -> Option<u16> {
+    (value != UNSUPPORTED_U16).then_some(value)
+}
+
+fn valid32(value: u32) -> Option<u32> {
+    (value != UNSUPPORTED_U32).then_some(value)
+}
+
+fn valid64(value: u64) -> Option<u64> {
+    (value != UNSUPPORTED_U64).then_some(value)
+}
+
+fn valid16s(values: Vec<u16>) -> Vec<u16> {
+    values.into_iter().filter(|value| *value != UNSUPPORTED_U16).collect()
+}
+
+// APUs report temperatures in centi-Celsius
+fn centi_celsius(value: u16) -> Option<u16> {
+    valid16(value).map(|value| value / 100)
+}
+
+// Power in W (dGPU) -> mW
+fn watts(value: u16) -> Option<u32> {
+    valid16(value).map(|value| u32::from(value) * 1000)
+}
+
+// Temperatures through current clocks of v1_0 (Navi 1x) and v1_1 - v1_3 (dGPU)
+fn decode_v1(r: &mut Reader, m: &mut GpuMetrics, content_revision: u8) -> Option<()> {
+    if content_revision == 0 {
+        m.system_clock_counter = valid64(r.u64()?);
+    }
+    m.temperature_edge = valid16(r.u16()?);
+    m.temperature_hotspot = valid16(r.u16()?);
+    m.temperature_mem = valid16(r.u16()?);
+    m.temperature_vrgfx = valid16(r.u16()?);
+    m.temperature_vrsoc = valid16(r.u16()?);
+    m.temperature_vrmem = valid16(r.u16()?);
+    m.average_gfx_activity = valid16(r.u16()?);
+    m.average_umc_activity = valid16(r.u16()?);
+    m.average_mm_activity = valid16(r.u16()?);
+    m.average_socket_power = watts(r.u16()?);
+    if content_revision == 0 {
+        m.energy_accumulator = valid32(r.u32()?).map(u64::from);
+    } else {
+        m.energy_accumulator = valid64(r.u64()?);
+        m.system_clock_counter = valid64(r.u64()?);
+    }
+    m.average_gfxclk_frequency = valid16(r.u16()?);
+    m.average_socclk_frequency = valid16(r.u16()?);
+    m.average_uclk_frequency = valid16(r.u16()?);
+    r.skip::<2>(4)?; // average vclk0/dclk0/vclk1/dclk1
+    m.current_gfxclk = valid16(r.u16()?);
+    m.current_socclk = valid16(r.u16()?);
+    m.current_uclk = valid16(r.u16()?);
+    r.skip::<2>(4)?; // current vclk0/dclk0/vclk1/dclk1
+    m.throttle_status = valid32(r.u32()?);
+    m.current_fan_speed = valid16(r.u16()?);
+    if content_revision == 0 {
+        m.pcie_link_width = Some(u16::from(r.u8()?));
+        m.pcie_link_speed = Some(u16::from(r.u8()?));
+        return Some(());
+    }
+    m.pcie_link_width = valid16(r.u16()?);
+    m.pcie_link_speed = valid16(r.u16()?);
+    r.skip::<2>(1)?; // padding
+    r.skip::<4>(2)?; // gfx/mem activity accumulators
+    r.skip::<2>(4)?; // temperature_hbm
+    if content_revision >= 2 {
+        m.firmware_timestamp = valid64(r.u64()?);
+    }
+    if content_revision >= 3 {
+        m.voltage_soc = valid16(r.u16()?);
+        m.voltage_gfx = valid16(r.u16()?);
+        m.voltage_mem = valid16(r.u16()?);
+        r.skip::<2>(1)?; // padding
+        m.indep_throttle_status = valid64(r.u64()?);
+    }
+    Some(())
+}
+
+// v1_4 and v1_5 (MI300)
+fn decode_v1_4(r: &mut Reader, m: &mut GpuMetrics, content_revision: u8) -> Option<()> {
+    m.temperature_hotspot = valid16(r.u16()?);
+    m.temperature_mem = valid16(r.u16()?);
+    m.temperature_vrsoc = valid16(r.u16()?);
+    m.average_socket_power = watts(r.u16()?);
+    m.average_gfx_activity = valid16(r.u16()?);
+    m.average_umc_activity = valid16(r.u16()?);
+    r.skip::<2>(4)?; // vcn_activity
+    if content_revision >= 5 {
+        r.skip::<2>(32)?; // jpeg_activity
+    }
+    m.energy_accumulator = valid64(r.u64()?);
+    m.system_clock_counter = valid64(r.u64()?);
+    m.throttle_status = valid32(r.u32()?);
+    r.skip::<4>(1)?; // gfxclk_lock_status
+    m.pcie_link_width = valid16(r.u16()?);
+    m.pcie_link_speed = valid16(r.u16()?);
+    r.skip::<2>(2)?; // xgmi link width/speed
+    r.skip::<4>(2)?; // gfx/mem activity accumulators
+    r.skip::<8>(5)?; // pcie bandwidth/replay counters
+    if content_revision >= 5 {
+        r.skip::<4>(2)?; // pcie nak counters
+    }
+    r.skip::<8>(16)?; // xgmi read/write data
+    m.firmware_timestamp = valid64(r.u64()?);
+    m.current_gfxclk = r.u16s(8)?.into_iter().find_map(valid16);
+    m.current_socclk = r.u16s(4)?.into_iter().find_map(valid16);
+    r.skip::<2>(8)?; // vclk0/dclk0
+    m.current_uclk = valid16(r.u16()?);
+    Some(())
+}
+
+// v2_0 - v2_4 (APU: Renoir, Van Gogh, Yellow Carp, ...)
+fn decode_v2(r: &mut Reader, m: &mut GpuMetrics, content_revision: u8) -> Option<()> {
+    if content_revision == 0 {
+        m.system_clock_counter = valid64(r.u64()?);
+    }
+    m.temperature_gfx = centi_celsius(r.u16()?);
+    m.temperature_soc = centi_celsius(r.u16()?);
+    m.temperature_core = valid16s(r.u16s(8)?).into_iter().map(|value| value / 100).collect();
+    r.skip::<2>(2)?; // temperature_l3
+    m.average_gfx_activity = valid16(r.u16()?);
+    m.average_mm_activity = valid16(r.u16()?);
+    if content_revision >= 1 {
+        m.system_clock_counter = valid64(r.u64()?);
+    }
+    m.average_socket_power = valid16(r.u16()?).map(u32::from);
+    r.skip::<2>(2)?; // average cpu/soc power
+    m.average_gfx_power = valid16(r.u16()?).map(u32::from);
+    r.skip::<2>(8)?; // average_core_power
+    m.average_gfxclk_frequency = valid16(r.u16()?);
+    m.average_socclk_frequency = valid16(r.u16()?);
+    m.average_uclk_frequency = valid16(r.u16()?);
+    m.average_fclk_frequency = valid16(r.u16()?);
+    r.skip::<2>(2)?; // average vclk/dclk
+    m.current_gfxclk = valid16(r.u16()?);
+    m.current_socclk = valid16(r.u16()?);
+    m.current_uclk = valid16(r.u16()?);
+    m.current_fclk = valid16(r.u16()?);
+    r.skip::<2>(2)?; // current vclk/dclk
+    m.current_coreclk = valid16s(r.u16s(8)?);
+    r.skip::<2>(2)?; // current_l3clk
+    m.throttle_status = valid32(r.u32()?);
+    m.fan_pwm = valid16(r.u16()?);
+    if content_revision >= 2 {
+        r.skip::<2>(3)?; // padding
+        m.indep_throttle_status = valid64(r.u64()?);
+    }
+    // v2_3 adds average temperatures and v2_4 average voltages/currents (not decoded)
+    Some(())
+}
+
+// v3_0 (APU: Phoenix, Strix)
+fn decode_v3(r: &mut Reader, m: &mut GpuMetrics) -> Option<()> {
+    m.temperature_gfx = centi_celsius(r.u16()?);
+    m.temperature_soc = centi_celsius(r.u16()?);
+    m.temperature_core = valid16s(r.u16s(16)?).into_iter().map(|value| value / 100).collect();
+    r.skip::<2>(1)?; // temperature_skin
+    m.average_gfx_activity = valid16(r.u16()?);
+    m.average_mm_activity = valid16(r.u16()?); // VCN
+    r.skip::<2>(8 + 16 + 4)?; // ipu/core activity, dram/ipu bandwidth
+    m.system_clock_counter = valid64(r.u64()?);
+    m.average_socket_power = valid32(r.u32()?);
+    r.skip::<2>(1)?; // average_ipu_power
+    r.skip::<4>(1)?; // average_apu_power
+    m.average_gfx_power = valid32(r.u32()?);
+    r.skip::<4>(2)?; // average dgpu/all core power
+    r.skip::<2>(16 + 2)?; // average_core_power, stapm limits
+    m.average_gfxclk_frequency = valid16(r.u16()?);
+    m.average_socclk_frequency = valid16(r.u16()?);
+    r.skip::<2>(2)?; // average vpeclk/ipuclk
+    m.average_fclk_frequency = valid16(r.u16()?);
+    r.skip::<2>(1)?; // average_vclk_frequency
+    m.average_uclk_frequency = valid16(r.u16()?);
+    r.skip::<2>(1)?; // average_mpipu_frequency
+    m.current_coreclk = valid16s(r.u16s(16)?);
+    Some(())
+}
+
+pub fn parse_gpu_metrics(data: &[u8]) -> Result<GpuMetrics, String> {
+    let mut r = Reader { data, offset: 0 };
+    let truncated = || format!("gpu_metrics is truncated ({} bytes)", data.len());
+    let structure_size = r.u16().ok_or_else(truncated)?;
+    let format_revision = r.u8().ok_or_else(truncated)?;
+    let content_revision = r.u8().ok_or_else(truncated)?;
+    if data.len() < usize::from(structure_size) {
+        return Err(truncated());
+    }
+    r.data = &data[..usize::from(structure_size)];
+
+    let mut metrics = GpuMetrics { format_revision, content_revision, ..Default::default() };
+    let decoded = match (format_revision, content_revision) {
+        (1, 0..=3) => decode_v1(&mut r, &mut metrics, content_revision),
+        (1, 4..=5) => decode_v1_4(&mut r, &mut metrics, content_revision),
+        (2, 0..=4) => decode_v2(&mut r, &mut metrics, content_revision),
+        (3, 0) => decode_v3(&mut r, &mut metrics),
+        _ => return Err(format!("Unsupported gpu_metrics revision v{}_{}", format_revision, content_revision)),
+    };
+    decoded.ok_or(format!("gpu_metrics v{}_{} is shorter than expected ({} bytes)",
+        format_revision, content_revision, structure_size))?;
+    Ok(metrics)
+}
+
+pub fn read_gpu_metrics(home_path: &Path) -> Option<Result<GpuMetrics, String>> {
+    let data = fs::read(home_path.join("gpu_metrics")).ok()?;
+    Some(parse_gpu_metrics(&data))
+}
+
+fn print_value<T: std::fmt::Display>(name: &str, value: Option<T>, unit: &str) {
+    if let Some(value) = value {
+        println!("{}: {}{}", name, value, unit);
+    }
+}
+
+pub fn print_gpu_metrics(metrics: &GpuMetrics) {
+    println!("GPU_METRICS (v{}_{}):", metrics.format_revision, metrics.content_revision);
+    print_value("TEMPERATURE_EDGE", metrics.temperature_edge, "C");
+    print_value("TEMPERATURE_HOTSPOT", metrics.temperature_hotspot, "C");
+    print_value("TEMPERATURE_MEM", metrics.temperature_mem, "C");
+    print_value("TEMPERATURE_VRGFX", metrics.temperature_vrgfx, "C");
+    print_value("TEMPERATURE_VRSOC", metrics.temperature_vrsoc, "C");
+    print_value("TEMPERATURE_VRMEM", metrics.temperature_vrmem, "C");
+    print_value("TEMPERATURE_GFX", metrics.temperature_gfx, "C");
+    print_value("TEMPERATURE_SOC", metrics.temperature_soc, "C");
+    if !metrics.temperature_core.is_empty() {
+        let cores: Vec<String> = metrics.temperature_core.iter().map(|t| format!("{}C", t)).collect();
+        println!("TEMPERATURE_CORE: {}", cores.join(" "));
+    }
+    print_value("GFX_ACTIVITY", metrics.average_gfx_activity, "%");
+    print_value("MEMORY_ACTIVITY", metrics.average_umc_activity, "%");
+    print_value("MEDIA_ACTIVITY", metrics.average_mm_activity, "%");
+    print_value("SOCKET_POWER", metrics.average_socket_power.map(|p| p as f64 / 1000.0), "W");
+    print_value("GFX_POWER", metrics.average_gfx_power.map(|p| p as f64 / 1000.0), "W");
+    print_value("ENERGY_ACCUMULATOR", metrics.energy_accumulator, "");
+    print_value("AVERAGE_GFXCLK", metrics.average_gfxclk_frequency, "Mhz");
+    print_value("AVERAGE_SOCCLK", metrics.average_socclk_frequency, "Mhz");
+    print_value("AVERAGE_UCLK", metrics.average_uclk_frequency, "Mhz");
+    print_value("AVERAGE_FCLK", metrics.average_fclk_frequency, "Mhz");
+    print_value("CURRENT_GFXCLK", metrics.current_gfxclk, "Mhz");
+    print_value("CURRENT_SOCCLK", metrics.current_socclk, "Mhz");
+    print_value("CURRENT_UCLK", metrics.current_uclk, "Mhz");
+    print_value("CURRENT_FCLK", metrics.current_fclk, "Mhz");
+    if !metrics.current_coreclk.is_empty() {
+        let cores: Vec<String> = metrics.current_coreclk.iter().map(|c| format!("{}Mhz", c)).collect();
+        println!("CURRENT_CORECLK: {}", cores.join(" "));
+    }
+    print_value("VOLTAGE_SOC", metrics.voltage_soc, "mV");
+    print_value("VOLTAGE_GFX", metrics.voltage_gfx, "mV");
+    print_value("VOLTAGE_MEM", metrics.voltage_mem, "mV");
+    print_value("THROTTLE_STATUS", metrics.throttle_status.map(|s| format!("{:#010x}", s)), "");
+    print_value("INDEP_THROTTLE_STATUS", metrics.indep_throttle_status.map(|s| format!("{:#018x}", s)), "");
+    print_value("FAN_SPEED", metrics.current_fan_speed, " RPM");
+    print_value("FAN_PWM", metrics.fan_pwm, "");
+    if let (Some(width), Some(speed)) = (metrics.pcie_link_width, metrics.pcie_link_speed) {
+        println!("PCIE_LINK: x{} {:.1}GT/s", width, speed as f64 / 10.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixtures are C structs (kgd_pp_interface.h) filled with the same distinct value per field
+    fn fixture(name: &str) -> GpuMetrics {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/gpu_metrics").join(name);
+        parse_gpu_metrics(&fs::read(path).unwrap()).unwrap()
+    }
+
+    fn assert_dgpu(m: &GpuMetrics) {
+        assert_eq!(m.temperature_edge, Some(45));
+        assert_eq!(m.temperature_hotspot, Some(58));
+        assert_eq!(m.temperature_mem, Some(62));
+        assert_eq!(m.temperature_vrmem, None); // Unsupported (0xffff)
+        assert_eq!(m.average_gfx_activity, Some(87));
+        assert_eq!(m.average_umc_activity, Some(34));
+        assert_eq!(m.average_socket_power, Some(212_000));
+        assert_eq!(m.energy_accumulator, Some(987_654_321));
+        assert_eq!(m.system_clock_counter, Some(123_456_789_012));
+        assert_eq!(m.average_gfxclk_frequency, Some(2450));
+        assert_eq!(m.current_gfxclk, Some(2487));
+        assert_eq!(m.current_uclk, Some(1250));
+        assert_eq!(m.throttle_status, Some(0x5));
+        assert_eq!(m.current_fan_speed, Some(1450));
+        assert_eq!(m.pcie_link_width, Some(16));
+        assert_eq!(m.pcie_link_speed, Some(160));
+    }
+
+    #[test]
+    fn v1_0() {
+        let m = fixture("v1_0.bin");
+        assert_eq!((m.format_revision, m.content_revision), (1, 0));
+        assert_dgpu(&m);
+        assert_eq!(m.firmware_timestamp, None);
+    }
+
+    #[test]
+    fn v1_1() {
+        let m = fixture("v1_1.bin");
+        assert_dgpu(&m);
+        assert_eq!(m.firmware_timestamp, None);
+    }
+
+    #[test]
+    fn v1_2() {
+        let m = fixture("v1_2.bin");
+        assert_dgpu(&m);
+        assert_eq!(m.firmware_timestamp, Some(555_000_111));
+        assert_eq!(m.indep_throttle_status, None);
+    }
+
+    #[test]
+    fn v1_3() {
+        let m = fixture("v1_3.bin");
+        assert_dgpu(&m);
+        assert_eq!(m.firmware_timestamp, Some(555_000_111));
+        assert_eq!((m.voltage_soc, m.voltage_gfx, m.voltage_mem), (Some(900), Some(1050), Some(1350)));
+        assert_eq!(m.indep_throttle_status, Some(0x1_0000_0001));
+    }
+
+    fn assert_mi300(m: &GpuMetrics) {
+        assert_eq!(m.temperature_hotspot, Some(58));
+        assert_eq!(m.temperature_mem, Some(62));
+        assert_eq!(m.average_socket_power, Some(550_000));
+        assert_eq!(m.average_gfx_activity, Some(87));
+        assert_eq!(m.energy_accumulator, Some(987_654_321));
+        assert_eq!(m.system_clock_counter, Some(123_456_789_012));
+        assert_eq!(m.throttle_status, Some(0x5));
+        assert_eq!(m.pcie_link_width, Some(16));
+        assert_eq!(m.firmware_timestamp, Some(555_000_111));
+        assert_eq!(m.current_gfxclk, Some(2487));
+        assert_eq!(m.current_socclk, Some(1201));
+        assert_eq!(m.current_uclk, Some(1250));
+    }
+
+    #[test]
+    fn v1_4() {
+        assert_mi300(&fixture("v1_4.bin"));
+    }
+
+    #[test]
+    fn v1_5() {
+        assert_mi300(&fixture("v1_5.bin"));
+    }
+
+    fn assert_apu(m: &GpuMetrics) {
+        assert_eq!(m.temperature_gfx, Some(51));
+        assert_eq!(m.temperature_soc, Some(49));
+        assert_eq!(m.temperature_core, vec![55]);
+        assert_eq!(m.average_gfx_activity, Some(87));
+        assert_eq!(m.average_socket_power, Some(15_500));
+        assert_eq!(m.system_clock_counter, Some(123_456_789_012));
+        assert_eq!(m.average_gfxclk_frequency, Some(2450));
+        assert_eq!(m.average_fclk_frequency, Some(1800));
+        assert_eq!(m.current_gfxclk, Some(2487));
+        assert_eq!(m.current_fclk, Some(1801));
+        assert_eq!(m.current_coreclk, vec![4200]);
+        assert_eq!(m.throttle_status, Some(0x5));
+        assert_eq!(m.fan_pwm, None); // Unsupported (0xffff)
+    }
+
+    #[test]
+    fn v2_0_and_v2_1() {
+        for name in ["v2_0.bin", "v2_1.bin"] {
+            let m = fixture(name);
+            assert_apu(&m);
+            assert_eq!(m.indep_throttle_status, None);
+        }
+    }
+
+    #[test]
+    fn v2_2_to_v2_4() {
+        for name in ["v2_2.bin", "v2_3.bin", "v2_4.bin"] {
+            let m = fixture(name);
+            assert_apu(&m);
+            assert_eq!(m.indep_throttle_status, Some(0x1_0000_0001));
+        }
+    }
+
+    #[test]
+    fn v3_0() {
+        let m = fixture("v3_0.bin");
+        assert_eq!((m.format_revision, m.content_revision), (3, 0));
+        assert_eq!(m.temperature_gfx, Some(51));
+        assert_eq!(m.temperature_core, vec![55]);
+        assert_eq!(m.average_gfx_activity, Some(87));
+        assert_eq!(m.system_clock_counter, Some(123_456_789_012));
+        assert_eq!(m.average_socket_power, Some(28_500));
+        assert_eq!(m.average_gfx_power, Some(12_000));
+        assert_eq!(m.average_gfxclk_frequency, Some(2450));
+        assert_eq!(m.average_uclk_frequency, Some(1249));
+        assert_eq!(m.current_coreclk, vec![4200]);
+    }
+
+    #[test]
+    fn truncated_and_unknown() {
+        let data = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/gpu_metrics/v1_3.bin")).unwrap();
+        assert!(parse_gpu_metrics(&data[..64]).is_err());
+        let mut unknown = data.clone();
+        unknown[2] = 9;
+        assert!(parse_gpu_metrics(&unknown).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Entry points for systemd (boot service and system-sleep hook)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::{Subcommand, ValueEnum};
+
+const SYSTEM_SLEEP_DIR: &str = "system-sleep";
+
+#[derive(Subcommand, Debug)]
+pub enum HookCommands {
+    /// Run at boot (restore the recorded profiles or the default profile)
+    Boot {
+        /// Seconds to wait for each device to show up
+        #[arg(long, default_value_t = 30)]
+        timeout: u64,
+    },
+    /// systemd-sleep hook (called as `hook sleep pre|post [suspend|hibernate|...]`)
+    Sleep {
+        phase: SleepPhase,
+        /// Sleep action passed by systemd-sleep
+        action: Option<String>,
+        /// Seconds to wait for each device to reappear after resume
+        #[arg(long, default_value_t = 10)]
+        timeout: u64,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SleepPhase {
+    Pre,
+    Post,
+}
+
+// systemd-sleep runs every executable in system-sleep/ as `<hook> pre|post <action>`, so a
+// symlink to this binary there is turned into `hook sleep pre|post <action>`
+pub fn systemd_sleep_args() -> Option<Vec<String>> {
+    let mut args = std::env::args();
+    let argv0 = PathBuf::from(args.next()?);
+    let parent = argv0.parent()?.file_name()?;
+    if parent != SYSTEM_SLEEP_DIR {
+        return None;
+    }
+    let mut hook_args = vec![
+        argv0.to_string_lossy().into_owned(),
+        String::from("hook"),
+        String::from("sleep"),
+    ];
+    hook_args.extend(args);
+    Some(hook_args)
+}
+
+pub fn run(command: HookCommands) {
+    match command {
+        HookCommands::Boot{timeout} => {
+            crate::restore_profiles(Some(Duration::from_secs(timeout)));
+        },
+        HookCommands::Sleep{phase: SleepPhase::Pre, ..} => {
+            // The active profiles are already recorded by `set`, nothing to save
+        },
+        HookCommands::Sleep{phase: SleepPhase::Post, action, timeout} => {
+            println!("Resuming from {}...", action.as_deref().unwrap_or("sleep"));
+            crate::restore_profiles(Some(Duration::from_secs(timeout)));
+        },
+    }
+}
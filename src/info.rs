@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Machine readable (json or yaml) info of a device for scripts, Ansible and dashboards
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::{capabilities, dpm, fan_ctrl, gpu_metrics, od, power_profile, state, throttle, DeviceConfig};
+use crate::fan_ctrl::{FanCtrlValue, FanCurve};
+use crate::gpu_metrics::GpuMetrics;
+use crate::od::OdTable;
+use crate::power_profile::PowerProfileTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum InfoFormat {
+    Text,
+    Json,
+    Yaml,
+}
+
+// Single value files in gpu_od/fan_ctrl (fan_curve has its own entry)
+const FAN_CTRL_FILES: [&str; 6] = [
+    "acoustic_limit_rpm_threshold",
+    "acoustic_target_rpm_threshold",
+    "fan_target_temperature",
+    "fan_minimum_pwm",
+    "fan_zero_rpm_enable",
+    "fan_zero_rpm_stop_temperature",
+];
+
+#[derive(Serialize)]
+struct Device {
+    card: Option<u8>,
+    unique_id: Option<String>,
+    pci_slot: String,
+    vendor_id: Option<String>,
+    device_id: Option<String>,
+    subsystem_vendor_id: Option<String>,
+    subsystem_device_id: Option<String>,
+    revision: Option<String>,
+    generation: String,
+    gc_version: Option<String>,
+    active_profile: Option<String>,
+}
+
+// Microwatts, same unit as POWER_CAP in the profile
+#[derive(Serialize)]
+struct PowerCap {
+    value: Option<u64>,
+    min: Option<u64>,
+    max: Option<u64>,
+    default: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Info {
+    device: Device,
+    performance_level: Option<String>,
+    power_profile: Option<PowerProfileTable>,
+    power_cap: PowerCap,
+    od: Option<OdTable>,
+    dpm: BTreeMap<String, Vec<dpm::DpmLevel>>,
+    fan_ctrl: BTreeMap<String, FanCtrlValue>,
+    fan_curve: Option<FanCurve>,
+    throttle_status: Option<Vec<String>>, // Active limiters (None if the card's bits can't be decoded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gpu_metrics: Option<GpuMetrics>,
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_owned())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+fn read_info(config: &DeviceConfig, metrics: bool) -> Info {
+    let home_path = &config.home_path;
+    let capabilities = capabilities::probe(home_path);
+    let pci_slot = state::device_key(home_path);
+    let device = Device {
+        card: config.card,
+        unique_id: read_string(&home_path.join("unique_id")),
+        active_profile: state::active_profile(&pci_slot),
+        pci_slot,
+        vendor_id: read_string(&home_path.join("vendor")),
+        device_id: read_string(&home_path.join("device")),
+        subsystem_vendor_id: read_string(&home_path.join("subsystem_vendor")),
+        subsystem_device_id: read_string(&home_path.join("subsystem_device")),
+        revision: read_string(&home_path.join("revision")),
+        generation: capabilities.generation.to_string(),
+        gc_version: capabilities.gc_version.map(|(major, minor, revision)| format!("{}.{}.{}", major, minor, revision)),
+    };
+
+    let hwmon = |name: &str| read_u64(&config.hwmon_path.join(name));
+    let power_cap = PowerCap {
+        value: hwmon("power1_cap"),
+        min: hwmon("power1_cap_min"),
+        max: hwmon("power1_cap_max"),
+        default: hwmon("power1_cap_default"),
+    };
+
+    let dpm = dpm::dpm_paths(home_path).iter().filter_map(|path| {
+        let file = path.file_name()?.to_string_lossy().into_owned();
+        let levels = dpm::read_dpm_levels(home_path, &file)?;
+        Some((file, levels))
+    }).collect();
+    let fan_ctrl = FAN_CTRL_FILES.iter()
+        .filter_map(|file| Some((file.to_string(), fan_ctrl::read_fan_ctrl_value(home_path, file)?)))
+        .collect();
+
+    let gpu_metrics = gpu_metrics::read_gpu_metrics(home_path).and_then(Result::ok);
+    let throttle_status = gpu_metrics.as_ref().and_then(throttle::active_limiters)
+        .map(|limiters| limiters.iter().map(|(name, _)| name.to_string()).collect());
+
+    Info {
+        device,
+        performance_level: read_string(&home_path.join("power_dpm_force_performance_level")),
+        power_profile: power_profile::read_power_profile_table(home_path),
+        power_cap,
+        od: od::read_od_table(home_path),
+        dpm,
+        fan_ctrl,
+        fan_curve: fan_ctrl::read_fan_curve(home_path),
+        throttle_status,
+        gpu_metrics: gpu_metrics.filter(|_| metrics),
+    }
+}
+
+pub fn print_info(config: &DeviceConfig, format: InfoFormat, metrics: bool) {
+    let info = read_info(config, metrics);
+    let output = match format {
+        InfoFormat::Json => serde_json::to_string_pretty(&info).expect("Failed to serialize device info"),
+        InfoFormat::Yaml => serde_norway::to_string(&info).expect("Failed to serialize device info"),
+        InfoFormat::Text => unreachable!("Text info is printed by read_card_settings"),
+    };
+    println!("{}", output.trim_end());
+}
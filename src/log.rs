@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Telemetry logging (csv or json lines) of the hwmon sensors, with rotation and a duration limit
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs::{self, File};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use clap::ValueEnum;
+use glob::glob;
+use crate::{gpu_metrics, state, throttle, DeviceConfig};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    Csv,
+    Jsonl,
+}
+
+pub struct LogOptions {
+    pub interval: Duration,
+    pub duration: Option<Duration>,
+    pub format: LogFormat,
+    pub output: Option<PathBuf>,
+    pub rotate_size: Option<u64>, // Bytes
+    pub keep: u32, // Rotated files to keep
+}
+
+// "250ms", "2s", "10m", "1h" (plain numbers are seconds)
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("Invalid duration {}", value))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        "h" => Ok(Duration::from_secs(number * 3600)),
+        _ => Err(format!("Invalid duration unit {} (ms, s, m or h)", unit)),
+    }
+}
+
+// "500K", "10M", "1G" (plain numbers are bytes)
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') | Some('k') => (&value[..value.len() - 1], 1 << 10),
+        Some('M') | Some('m') => (&value[..value.len() - 1], 1 << 20),
+        Some('G') | Some('g') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    number.parse::<u64>().map(|number| number * multiplier).map_err(|_| format!("Invalid size {}", value))
+}
+
+// Every readable hwmon sensor (temp*_input, freq*_input, power1_average, pwm1, ...) in raw sysfs units
+fn hwmon_sensors(hwmon_path: &Path) -> Vec<String> {
+    let mut sensors: Vec<String> = ["*_input", "*_average", "pwm1"].iter()
+        .flat_map(|pattern| glob(hwmon_path.join(pattern).to_str().unwrap()).expect("Failed to detect hwmon sensors"))
+        .flatten()
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    sensors.sort();
+    sensors.dedup();
+    sensors
+}
+
+fn read_value(path: &Path) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    value.parse::<i64>().is_ok().then(|| value.to_owned())
+}
+
+// Profile names are file names and may contain commas or quotes, so quote them like csv does
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).expect("Failed to serialize the log")
+}
+
+struct LogWriter {
+    output: Option<PathBuf>,
+    writer: Box<dyn Write>,
+    written: u64,
+}
+
+impl LogWriter {
+    fn open(output: &Option<PathBuf>) -> io::Result<Self> {
+        let writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(LineWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout()),
+        };
+        Ok(LogWriter { output: output.clone(), writer, written: 0 })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    // run.csv -> run.csv.1 -> run.csv.2 ... (the oldest beyond keep is dropped)
+    fn rotate(&mut self, keep: u32) -> io::Result<()> {
+        let Some(path) = self.output.clone() else {
+            return Ok(());
+        };
+        self.writer.flush()?;
+        let rotated = |n: u32| PathBuf::from(format!("{}.{}", path.display(), n));
+        let _ = fs::remove_file(rotated(keep));
+        for n in (1..keep).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        if keep > 0 {
+            fs::rename(&path, rotated(1))?;
+        }
+        *self = LogWriter::open(&self.output)?;
+        Ok(())
+    }
+}
+
+pub fn run(config: &DeviceConfig, options: LogOptions) {
+    let sensors = hwmon_sensors(&config.hwmon_path);
+    let device = state::device_key(&config.home_path);
+    let has_metrics = gpu_metrics::read_gpu_metrics(&config.home_path).is_some_and(|metrics| metrics.is_ok());
+    let header = {
+        let mut columns = vec!["timestamp", "elapsed", "profile", "gpu_busy_percent"];
+        columns.extend(sensors.iter().map(String::as_str));
+        if has_metrics {
+            columns.push("throttle");
+        }
+        columns.join(",")
+    };
+
+    let mut writer = LogWriter::open(&options.output).expect("Can't create the log file");
+    if let LogFormat::Csv = options.format {
+        writer.write_line(&header).expect("Failed to write the log");
+    }
+
+    let start = Instant::now();
+    while options.duration.is_none_or(|duration| start.elapsed() < duration) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        // The profile can change while logging (e.g. a benchmark script switching profiles)
+        let profile = state::active_profile(&device).unwrap_or_default();
+        let mut values: Vec<(&str, Option<String>)> = vec![("gpu_busy_percent", read_value(&config.home_path.join("gpu_busy_percent")))];
+        values.extend(sensors.iter().map(|sensor| (sensor.as_str(), read_value(&config.hwmon_path.join(sensor)))));
+        let throttle = has_metrics.then(|| {
+            gpu_metrics::read_gpu_metrics(&config.home_path)
+                .and_then(Result::ok)
+                .map(|metrics| throttle::short_description(&metrics))
+                .unwrap_or_default()
+        });
+
+        let line = match options.format {
+            LogFormat::Csv => {
+                let mut line = vec![format!("{:.3}", timestamp), format!("{:.3}", start.elapsed().as_secs_f64()), csv_field(&profile)];
+                line.extend(values.into_iter().map(|(_, value)| value.unwrap_or_default()));
+                line.extend(throttle);
+                line.join(",")
+            },
+            LogFormat::Jsonl => {
+                let mut fields = vec![
+                    format!("\"timestamp\":{:.3}", timestamp),
+                    format!("\"elapsed\":{:.3}", start.elapsed().as_secs_f64()),
+                    format!("\"profile\":{}", json_string(&profile)),
+                ];
+                fields.extend(values.into_iter().filter_map(|(name, value)| Some(format!("\"{}\":{}", name, value?))));
+                fields.extend(throttle.map(|throttle| format!("\"throttle\":{}", json_string(&throttle))));
+                format!("{{{}}}", fields.join(","))
+            },
+        };
+        writer.write_line(&line).expect("Failed to write the log");
+
+        if options.rotate_size.is_some_and(|size| writer.written >= size) {
+            writer.rotate(options.keep).expect("Failed to rotate the log");
+            if let LogFormat::Csv = options.format {
+                writer.write_line(&header).expect("Failed to write the log");
+            }
+        }
+        thread::sleep(options.interval);
+    }
+}
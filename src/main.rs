@@ -8,12 +8,40 @@
 
 use std::fs;
 use std::fs::{File, OpenOptions};
-use std::path::PathBuf;
-use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
 use clap::{Parser, Subcommand};
 use glob::glob;
 
+mod alert;
+mod capabilities;
+mod doctor;
+mod dpm;
+mod energy;
+mod exporter;
+mod fan_ctrl;
+mod fan_daemon;
+mod gpu_metrics;
+mod hook;
+mod info;
+mod log;
+mod monitor;
+mod od;
+mod power_profile;
+mod sensors;
+mod state;
+mod systemd;
+mod throttle;
+mod tui;
+mod udev;
+
 const CONFIG_PROFILE_PATH: &str = "/etc/default/amdgpu-settings.";
+const BUSY_RETRIES: u32 = 10;
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(200);
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Default, Debug)]
 struct DeviceConfig {
@@ -23,56 +51,123 @@ struct DeviceConfig {
     hwmon_path: PathBuf,
     performance_level: Option<String>,
     power_profile_index: Option<u8>,
-    od_sclk_min: Option<u32>, // RDNA 2 and 3
-    od_sclk_max: Option<u32>, // RDNA 2 and 3
+    power_profile: Option<String>,
+    power_profile_custom: Vec<power_profile::CustomHeuristics>,
+    od_sclk: Vec<od::OdState>, // RDNA 3 or older (min/max, or every state on Vega/Polaris)
     od_sclk_offset: Option<i32>, // RDNA 4
-    od_mclk_min: Option<u32>,
-    od_mclk_max: Option<u32>,
+    od_mclk: Vec<od::OdState>,
     od_vddgfx_offset: Option<i32>,
+    od_vddc_curve: Vec<(u8, u32, u32)>, // RDNA 1 and some RDNA 2 (point, sclk, mV)
     power_cap: Option<u64>,
     acoustic_limit_rpm_threshold: Option<u32>,
     acoustic_target_rpm_threshold: Option<u32>,
     fan_target_temp: Option<u32>,
     fan_zero_rpm: Option<u8>,
     fan_zero_rpm_stop_temp: Option<u32>,
+    fan_curve: Vec<fan_ctrl::FanCurvePoint>,
+    fan_minimum_pwm: Option<u32>,
+    software_fan_curve: Vec<fan_daemon::SoftwareFanCurvePoint>,
+    software_fan_sensor: Option<String>,
+    software_fan_hysteresis: Option<u32>,
+    software_fan_spin_up_rate: Option<u32>,
+    software_fan_spin_down_rate: Option<u32>,
+    software_fan_interval: Option<u64>,
+    alerts: Vec<alert::Alert>,
+    alert_command: Option<String>,
+    alert_fallback_profile: Option<String>,
+    fallback_profile: Option<String>,
+    fallback_triggers: Vec<alert::Alert>,
+    fallback_hysteresis: Option<u32>,
+    fallback_cooldown: Option<Duration>,
+    dpm_masks: Vec<(String, Vec<u8>)>, // (DPM_* key, levels)
 }
 
-fn validate_detect_mount_points(config: &mut DeviceConfig) {
-    let path: PathBuf = if let Some(card) = config.card {
+// Returns the drm card path (e.g. /sys/class/drm/card1) of the profile's target device
+fn find_card_path(config: &DeviceConfig) -> Option<PathBuf> {
+    if let Some(card) = config.card {
         // Simple card #
-        PathBuf::from(format!("/sys/class/drm/card{}", card))
-    } else {
-        // Match by unique_id
-        let unique_id = config.device_id.expect("Invalid UNIQUE_ID");
-
-        let mut found_path: Option<PathBuf> = None;
-        for entry in glob("/sys/class/drm/card*").expect("Failed to detect drm card path") {
-            if let Ok(card_path) = entry {
-                let unique_id_path = card_path.join("device/unique_id");
-                if let Ok(target_id_str) = fs::read_to_string(unique_id_path) {
-                    let target_id = u64::from_str_radix(target_id_str.trim(), 16)
-                        .expect("Malformed unique_id");
-                    if target_id == unique_id {
-                        found_path = Some(card_path);
-                        break;
-                    }
-                }
+        let path = PathBuf::from(format!("/sys/class/drm/card{}", card));
+        return path.exists().then_some(path);
+    }
+    // Match by unique_id
+    let unique_id = config.device_id.expect("Invalid UNIQUE_ID");
+    for card_path in glob("/sys/class/drm/card*").expect("Failed to detect drm card path").flatten() {
+        let unique_id_path = card_path.join("device/unique_id");
+        if let Ok(target_id_str) = fs::read_to_string(unique_id_path) {
+            let target_id = u64::from_str_radix(target_id_str.trim(), 16)
+                .expect("Malformed unique_id");
+            if target_id == unique_id {
+                return Some(card_path);
             }
         }
-        found_path.expect("Fatal error: Unable to locate card mount point by unique_id. Please check /sys/class/drm")
-    };
+    }
+    None
+}
 
-    config.home_path = path.join("device");
-    let hwmon_pattern = path.join("device/hwmon/hwmon*");
-    for entry in glob(hwmon_pattern.to_str().unwrap()).expect("Failed to detect hwmon path") {
-        if let Ok(hwmon_path) = entry {
-            config.hwmon_path = hwmon_path;
-            return;
+fn find_hwmon_path(card_path: &Path) -> Option<PathBuf> {
+    let hwmon_pattern = card_path.join("device/hwmon/hwmon*");
+    glob(hwmon_pattern.to_str().unwrap()).expect("Failed to detect hwmon path").flatten().next()
+}
+
+// The card shows up before amdgpu has finished creating hwmon and the overdrive files
+fn device_ready(home_path: &Path) -> bool {
+    if !home_path.join("pp_od_clk_voltage").exists() {
+        return false;
+    }
+    let hwmon_pattern = home_path.join("hwmon/hwmon*");
+    glob(hwmon_pattern.to_str().unwrap())
+        .map(|mut paths| paths.any(|p| p.is_ok()))
+        .unwrap_or(false)
+}
+
+fn wait_for_device(path: &str, timeout: Duration) -> Result<(), String> {
+    let lines = read_profile_lines(path);
+    let config = parse_profile_target(&lines);
+    let start = Instant::now();
+    loop {
+        if let Some(card_path) = find_card_path(&config) {
+            if device_ready(&card_path.join("device")) {
+                return Ok(());
+            }
+        }
+        if start.elapsed() >= timeout {
+            return Err(format!("Timed out after {}s waiting for the device of {} (card, hwmon and pp_od_clk_voltage). Please check /sys/class/drm",
+                timeout.as_secs(), path));
         }
+        thread::sleep(DEVICE_POLL_INTERVAL);
+    }
+}
+
+// Commands working on a single device give up if it never shows up
+fn wait_for_device_or_exit(path: &str, timeout: Duration) {
+    if let Err(e) = wait_for_device(path, timeout) {
+        eprintln!("{}", e);
+        doctor::report_to_stderr();
+        std::process::exit(1);
     }
-    panic!("Unable to detect hwmon_path!!!");
 }
 
+fn validate_detect_mount_points(config: &mut DeviceConfig) {
+    let path = find_card_path(config)
+        .expect("Fatal error: Unable to locate card mount point. Please check /sys/class/drm");
+
+    config.home_path = path.join("device");
+    config.hwmon_path = find_hwmon_path(&path).expect("Unable to detect hwmon_path!!!");
+}
+
+// The driver answers EBUSY while the GPU is still coming back from suspend, so retry a few times
+fn write_sysfs(file: &mut File, value: &[u8]) -> io::Result<()> {
+    let mut attempt = 0;
+    loop {
+        match file.write_all(value) {
+            Err(e) if e.kind() == ErrorKind::ResourceBusy && attempt < BUSY_RETRIES => {
+                attempt += 1;
+                thread::sleep(BUSY_RETRY_DELAY);
+            },
+            result => return result,
+        }
+    }
+}
 
 fn parse_performance_level(config: &mut DeviceConfig, lines: &[String]) {
     let value = lines[1].parse().expect("Invalid PERFORMANCE_LEVEL");
@@ -84,54 +179,49 @@ fn parse_power_profile_index(config: &mut DeviceConfig, lines: &[String]) {
     config.power_profile_index = Some(value);
 }
 
+fn parse_power_profile(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim().parse().expect("Invalid POWER_PROFILE");
+    config.power_profile = Some(value);
+}
+
+fn parse_power_profile_custom(config: &mut DeviceConfig, lines: &[String]) {
+    let mut i: usize = 1;
+
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        let heuristics = power_profile::parse_custom_heuristics(&lines[i])
+            .expect("Invalid POWER_PROFILE_CUSTOM line");
+        config.power_profile_custom.push(heuristics);
+        i += 1;
+    }
+}
+
 // RDNA 4 core clk speed
 fn parse_od_sclk_offset(config: &mut DeviceConfig, lines: &[String]) {
-    let sclk_offset: i32 = (&lines[1].split("M").collect::<Vec<&str>>()[0])
+    let sclk_offset: i32 = lines[1].split("M").collect::<Vec<&str>>()[0]
         .parse().expect("Invalid OD_SCLK_OFFSET option");
     config.od_sclk_offset = Some(sclk_offset);
 }
 
-// RDNA 3 or older clk speed
+// RDNA 3 or older clk speed ("0: 500Mhz", or "7: 1600Mhz 1150mV" per state on Vega/Polaris)
 fn parse_od_sclk(config: &mut DeviceConfig, lines: &[String]) {
-    let mut i: usize = 1;
-
-    while lines[i].len() != 0 {
-        let sclk: (char, u32) = (
-            lines[i].chars().nth(0).expect("Invalid OD_SCLK option"),
-            (&lines[i][3..].split("M").collect::<Vec<&str>>()[0])
-                .parse().expect("Invalid OD_SCLK option")
-        );
-        match sclk.0 {
-            '0' => config.od_sclk_min = Some(sclk.1),
-            '1' => config.od_sclk_max = Some(sclk.1),
-            _ => {
-                println!("Invalid OD_SCLK option");
-                return;
-            },
-        }
-        i += 1;
-    }
+    config.od_sclk = parse_od_states("OD_SCLK", lines);
 }
 
 fn parse_od_mclk(config: &mut DeviceConfig, lines: &[String]) {
+    config.od_mclk = parse_od_states("OD_MCLK", lines);
+}
+
+fn parse_od_states(key: &str, lines: &[String]) -> Vec<od::OdState> {
+    let mut states: Vec<od::OdState> = Vec::new();
     let mut i: usize = 1;
 
-    while lines[i].len() != 0 {
-        let mclk: (char, u32) = (
-            lines[i].chars().nth(0).expect("Invalid OD_MCLK option"),
-            (&lines[i][3..].split("M").collect::<Vec<&str>>()[0])
-                .parse().expect("Invalid OD_MCLK option")
-        );
-        match mclk.0 {
-            '0' => config.od_mclk_min = Some(mclk.1),
-            '1' => config.od_mclk_max = Some(mclk.1),
-            _ => {
-                println!("Invalid OD_MCLK option");
-                return;
-            },
-        }
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        let state = od::parse_od_state(&lines[i])
+            .unwrap_or_else(|| panic!("Invalid {} option (expected \"[state]: [clk]Mhz [voltage]mV\")", key));
+        states.push(state);
         i += 1;
     }
+    states
 }
 
 fn parse_od_vddgfx_offset(config: &mut DeviceConfig, lines: &[String]) {
@@ -141,6 +231,24 @@ fn parse_od_vddgfx_offset(config: &mut DeviceConfig, lines: &[String]) {
     config.od_vddgfx_offset = Some(value);
 }
 
+// RDNA 1 and some RDNA 2 voltage curve ("0: 800Mhz 711mV")
+fn parse_od_vddc_curve(config: &mut DeviceConfig, lines: &[String]) {
+    let mut i: usize = 1;
+
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        let (point, values) = lines[i].split_once(':').expect("Invalid OD_VDDC_CURVE option");
+        let values: Vec<u32> = values.split_whitespace()
+            .map(|v| v.trim_end_matches(char::is_alphabetic).parse().expect("Invalid OD_VDDC_CURVE option"))
+            .collect();
+        if values.len() != 2 {
+            panic!("Invalid OD_VDDC_CURVE option (expected \"[point]: [clk]Mhz [voltage]mV\")");
+        }
+        let point = point.trim().parse().expect("Invalid OD_VDDC_CURVE point");
+        config.od_vddc_curve.push((point, values[0], values[1]));
+        i += 1;
+    }
+}
+
 fn parse_power_cap(config: &mut DeviceConfig, lines: &[String]) {
     let value = lines[1].parse().expect("Invalid POWER_CAP");
     config.power_cap = Some(value);
@@ -171,12 +279,31 @@ fn parse_fan_zero_rpm_stop_temp(config: &mut DeviceConfig, lines: &[String]) {
     config.fan_zero_rpm_stop_temp = Some(value);
 }
 
-fn parse_profile(path: &str) -> DeviceConfig {
+// Names of every /etc/default/amdgpu-settings.[PROFILE_NAME]
+fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = glob(&(CONFIG_PROFILE_PATH.to_owned() + "*"))
+        .expect("Failed to list profiles")
+        .flatten()
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let path = path.to_string_lossy().into_owned();
+            path.strip_prefix(CONFIG_PROFILE_PATH).map(String::from)
+        })
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+fn read_profile_lines(path: &str) -> Vec<String> {
     let file = File::open(path).expect("Profile not found");
-    let lines: Vec<String> = BufReader::new(file)
+    BufReader::new(file)
         .lines()
         .map(|l| l.expect("Can't parse line"))
-        .collect();
+        .collect()
+}
+
+// Only parses the CARD/UNIQUE_ID line (no sysfs access)
+fn parse_profile_target(lines: &[String]) -> DeviceConfig {
     let id_str: &str = &lines[0];
     let (id_type, id) = id_str.split_once(char::is_whitespace)
         .expect("Error parsing CARD/UNIQUE_ID");
@@ -186,29 +313,172 @@ fn parse_profile(path: &str) -> DeviceConfig {
         "UNIQUE_ID:" => config.device_id = Some(u64::from_str_radix(id.trim(), 16).expect("Invalid UNIQUE_ID #")),
         _ => panic!("Unknown target device: Check /sys/class/drm"),
     }
+    config
+}
+
+fn parse_fan_minimum_pwm(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim_end_matches('%').parse().expect("Invalid FAN_MINIMUM_PWM");
+    config.fan_minimum_pwm = Some(value);
+}
+
+fn parse_fan_curve(config: &mut DeviceConfig, lines: &[String]) {
+    let mut i: usize = 1;
+
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        let point = fan_ctrl::parse_fan_curve_point(&lines[i]).expect("Invalid FAN_CURVE point");
+        config.fan_curve.push(point);
+        i += 1;
+    }
+}
+
+fn parse_software_fan_curve(config: &mut DeviceConfig, lines: &[String]) {
+    let mut i: usize = 1;
+
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        let point = fan_daemon::parse_software_fan_curve_point(&lines[i])
+            .expect("Invalid SOFTWARE_FAN_CURVE point");
+        config.software_fan_curve.push(point);
+        i += 1;
+    }
+}
+
+fn parse_software_fan_sensor(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim().parse().expect("Invalid SOFTWARE_FAN_SENSOR");
+    config.software_fan_sensor = Some(value);
+}
+
+fn parse_software_fan_hysteresis(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim_end_matches('C').parse().expect("Invalid SOFTWARE_FAN_HYSTERESIS");
+    config.software_fan_hysteresis = Some(value);
+}
+
+fn parse_software_fan_spin_up_rate(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim_end_matches('%').parse().expect("Invalid SOFTWARE_FAN_SPIN_UP_RATE");
+    config.software_fan_spin_up_rate = Some(value);
+}
+
+fn parse_software_fan_spin_down_rate(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim_end_matches('%').parse().expect("Invalid SOFTWARE_FAN_SPIN_DOWN_RATE");
+    config.software_fan_spin_down_rate = Some(value);
+}
+
+fn parse_software_fan_interval(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim_end_matches("ms").parse().expect("Invalid SOFTWARE_FAN_INTERVAL");
+    config.software_fan_interval = Some(value);
+}
+
+fn parse_alert_lines(key: &str, lines: &[String]) -> Vec<alert::Alert> {
+    let mut alerts = Vec::new();
+    let mut i: usize = 1;
+
+    while i < lines.len() && !lines[i].trim().is_empty() {
+        alerts.push(alert::parse_alert(&lines[i]).unwrap_or_else(|e| panic!("Invalid {}: {}", key, e)));
+        i += 1;
+    }
+    alerts
+}
+
+fn parse_alerts(config: &mut DeviceConfig, lines: &[String]) {
+    config.alerts = parse_alert_lines("ALERT", lines);
+}
+
+fn parse_alert_command(config: &mut DeviceConfig, lines: &[String]) {
+    config.alert_command = Some(lines[1].trim().to_owned());
+}
+
+fn parse_alert_fallback_profile(config: &mut DeviceConfig, lines: &[String]) {
+    config.alert_fallback_profile = Some(lines[1].trim().to_owned());
+}
+
+fn parse_fallback_profile(config: &mut DeviceConfig, lines: &[String]) {
+    config.fallback_profile = Some(lines[1].trim().to_owned());
+}
+
+// FALLBACK_HYSTERESIS is in C, so only temperatures and throttle conditions can be triggers
+fn parse_fallback_triggers(config: &mut DeviceConfig, lines: &[String]) {
+    config.fallback_triggers = parse_alert_lines("FALLBACK_TRIGGER", lines);
+    for trigger in &config.fallback_triggers {
+        for condition in &trigger.conditions {
+            if let alert::Condition::Threshold(sensor, _, _) = condition {
+                if !sensor.is_temperature() {
+                    panic!("Invalid FALLBACK_TRIGGER: {} (only temperatures and throttle has [limiter])", trigger.text);
+                }
+            }
+        }
+    }
+}
+
+fn parse_fallback_hysteresis(config: &mut DeviceConfig, lines: &[String]) {
+    let value = lines[1].trim().trim_end_matches('C').parse().expect("Invalid FALLBACK_HYSTERESIS");
+    config.fallback_hysteresis = Some(value);
+}
+
+fn parse_fallback_cooldown(config: &mut DeviceConfig, lines: &[String]) {
+    let value = log::parse_duration(lines[1].trim()).expect("Invalid FALLBACK_COOLDOWN");
+    config.fallback_cooldown = Some(value);
+}
+
+// Accepts both "DPM_SCLK: 1 2" and the value on the next line
+fn parse_dpm_mask(config: &mut DeviceConfig, lines: &[String]) {
+    let (key, inline_value) = lines[0].trim().split_once(':').expect("Invalid DPM option");
+    if dpm::dpm_file(key).is_none() {
+        panic!("Unknown DPM option {}", key);
+    }
+    let value = if inline_value.trim().is_empty() { lines[1].trim() } else { inline_value.trim() };
+    let mask = value.split_whitespace()
+        .map(|level| level.parse().expect("Invalid DPM level"))
+        .collect();
+    config.dpm_masks.push((key.to_owned(), mask));
+}
+
+fn parse_profile(path: &str) -> DeviceConfig {
+    parse_profile_lines(&read_profile_lines(path))
+}
+
+fn parse_profile_lines(lines: &[String]) -> DeviceConfig {
+    let mut config = parse_profile_target(lines);
     validate_detect_mount_points(&mut config);
 
     let mut i: usize = 0;
     while i < lines.len() {
-        let line: &str = &lines[i].trim();
+        let line: &str = lines[i].trim();
         match line {
             "PERFORMANCE_LEVEL:" => parse_performance_level(&mut config, &lines[i..]),
             "POWER_PROFILE_INDEX:" => parse_power_profile_index(&mut config, &lines[i..]),
+            "POWER_PROFILE:" => parse_power_profile(&mut config, &lines[i..]),
+            "POWER_PROFILE_CUSTOM:" => parse_power_profile_custom(&mut config, &lines[i..]),
             "OD_SCLK_OFFSET:" => parse_od_sclk_offset(&mut config, &lines[i..]),
             "OD_SCLK:" => parse_od_sclk(&mut config, &lines[i..]),
             "OD_MCLK:" => parse_od_mclk(&mut config, &lines[i..]),
             "OD_VDDGFX_OFFSET:" => parse_od_vddgfx_offset(&mut config, &lines[i..]),
+            "OD_VDDC_CURVE:" => parse_od_vddc_curve(&mut config, &lines[i..]),
             "POWER_CAP:" => parse_power_cap(&mut config, &lines[i..]),
             "OD_ACOUSTIC_LIMIT:" => parse_acoustic_limit_rpm_threshold(&mut config, &lines[i..]),
             "OD_ACOUSTIC_TARGET:" => parse_acoustic_target_rpm_threshold(&mut config, &lines[i..]),
             "FAN_TARGET_TEMPERATURE:" => parse_fan_target_temp(&mut config, &lines[i..]),
             "FAN_ZERO_RPM_ENABLE:" => parse_fan_zero_rpm(&mut config, &lines[i..]),
             "FAN_ZERO_RPM_STOP_TEMPERATURE:" => parse_fan_zero_rpm_stop_temp(&mut config, &lines[i..]),
+            "FAN_CURVE:" => parse_fan_curve(&mut config, &lines[i..]),
+            "FAN_MINIMUM_PWM:" => parse_fan_minimum_pwm(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_CURVE:" => parse_software_fan_curve(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_SENSOR:" => parse_software_fan_sensor(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_HYSTERESIS:" => parse_software_fan_hysteresis(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_SPIN_UP_RATE:" => parse_software_fan_spin_up_rate(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_SPIN_DOWN_RATE:" => parse_software_fan_spin_down_rate(&mut config, &lines[i..]),
+            "SOFTWARE_FAN_INTERVAL:" => parse_software_fan_interval(&mut config, &lines[i..]),
+            "ALERT:" => parse_alerts(&mut config, &lines[i..]),
+            "ALERT_COMMAND:" => parse_alert_command(&mut config, &lines[i..]),
+            "ALERT_FALLBACK_PROFILE:" => parse_alert_fallback_profile(&mut config, &lines[i..]),
+            "FALLBACK_PROFILE:" => parse_fallback_profile(&mut config, &lines[i..]),
+            "FALLBACK_TRIGGER:" => parse_fallback_triggers(&mut config, &lines[i..]),
+            "FALLBACK_HYSTERESIS:" => parse_fallback_hysteresis(&mut config, &lines[i..]),
+            "FALLBACK_COOLDOWN:" => parse_fallback_cooldown(&mut config, &lines[i..]),
+            _ if line.starts_with("DPM_") => parse_dpm_mask(&mut config, &lines[i..]),
             _ => {}
         }
         i += 1;
     }
-    return config;
+    config
 }
 
 fn apply_settings(name: &str, mut config: DeviceConfig) {
@@ -221,150 +491,270 @@ fn apply_settings(name: &str, mut config: DeviceConfig) {
         .open(config.home_path.join("power_dpm_force_performance_level"))
         .expect("Can't access power_dpm_force_performance_level file");
     if config.performance_level.is_some() {
-        file.write_all(config.performance_level.as_deref().unwrap_or("manual").as_bytes())
+        write_sysfs(&mut file, config.performance_level.as_deref().unwrap_or("manual").as_bytes())
             .expect("Failed to write power_dpm_force_performance_level");
     } else {
-        file.write_all("manual".as_bytes())
+        write_sysfs(&mut file, "manual".as_bytes())
             .expect("Failed to write power_dpm_force_performance_level to \"manual\"");
     }
 
     // POWER_CAP (Side effect of writing a new value will reset GPU settings. Should set this
     // before adjusting the other settings)
-    if config.power_cap.is_some() {
+    if let Some(power_cap) = config.power_cap {
         let mut file = OpenOptions::new().write(true)
             .open(config.hwmon_path.join("power1_cap"))
             .expect("Can't access power1_cap file");
-        file.write_all(config.power_cap.unwrap().to_string().as_bytes())
+        write_sysfs(&mut file, power_cap.to_string().as_bytes())
             .expect("Failed to set POWER_CAP");
     }
 
+    // POWER_PROFILE_CUSTOM (Heuristics first, then select the profile. CUSTOM is selected if no
+    // other profile is given)
+    let mut power_profile_index = config.power_profile_index;
+    if power_profile_index.is_some() || config.power_profile.is_some() || !config.power_profile_custom.is_empty() {
+        let table = power_profile::read_power_profile_table(&config.home_path)
+            .expect("Can't access pp_power_profile_mode file");
+        let commands = power_profile::custom_commands(&table, &config.power_profile_custom)
+            .unwrap_or_else(|e| panic!("{}", e));
+        for command in commands {
+            let mut file = OpenOptions::new().write(true)
+                .open(config.home_path.join("pp_power_profile_mode"))
+                .expect("Can't access pp_power_profile_mode file");
+            write_sysfs(&mut file, command.as_bytes())
+                .expect("Failed to write POWER_PROFILE_CUSTOM");
+        }
+        // POWER_PROFILE (by name, takes precedence over POWER_PROFILE_INDEX)
+        let name = match &config.power_profile {
+            Some(name) => name.as_str(),
+            None if power_profile_index.is_none() => power_profile::CUSTOM,
+            None => "",
+        };
+        if !name.is_empty() {
+            let mode = table.find(name)
+                .unwrap_or_else(|| panic!("Card has no {} power profile (check pp_power_profile_mode)", name));
+            power_profile_index = Some(mode.index);
+        }
+    }
+
     // POWER_PROFILE_INDEX
-    if config.power_profile_index.is_some() {
+    if let Some(power_profile_index) = power_profile_index {
         let mut file = OpenOptions::new().write(true)
             .open(config.home_path.join("pp_power_profile_mode"))
             .expect("Can't access pp_power_profile_mode file");
-        file.write_all(config.power_profile_index.unwrap().to_string().as_bytes())
+        write_sysfs(&mut file, power_profile_index.to_string().as_bytes())
             .expect("Failed to set POWER_PROFILE_INDEX");
     }
 
     // OD_ACOUSTIC_LIMIT
-    if config.acoustic_limit_rpm_threshold.is_some() {
+    if let Some(acoustic_limit_rpm_threshold) = config.acoustic_limit_rpm_threshold {
         let mut file = OpenOptions::new().write(true)
             .open(config.home_path.join("gpu_od/fan_ctrl/acoustic_limit_rpm_threshold"))
             .expect("Can't access acoustic_limit_rpm_threshold file");
-        file.write_all(format!("{}\n", config.acoustic_limit_rpm_threshold.unwrap()).as_bytes())
+        write_sysfs(&mut file, format!("{}\n", acoustic_limit_rpm_threshold).as_bytes())
             .expect("Failed to write OD_ACOUSTIC_LIMIT");
     }
     // OD_ACOUSTIC_TARGET
-    if config.acoustic_target_rpm_threshold.is_some() {
+    if let Some(acoustic_target_rpm_threshold) = config.acoustic_target_rpm_threshold {
         let mut file = OpenOptions::new().write(true)
             .open(config.home_path.join("gpu_od/fan_ctrl/acoustic_target_rpm_threshold"))
             .expect("Can't access acoustic_target_rpm_threshold file");
-        file.write_all(format!("{}\n", config.acoustic_target_rpm_threshold.unwrap()).as_bytes())
+        write_sysfs(&mut file, format!("{}\n", acoustic_target_rpm_threshold).as_bytes())
             .expect("Failed to write OD_ACOUSTIC_TARGET");
     }
 
     // FAN_TARGET_TEMPERATURE 
-    if config.fan_target_temp.is_some() {
+    if let Some(fan_target_temp) = config.fan_target_temp {
         let mut file = OpenOptions::new().write(true)
             .open(config.home_path.join("gpu_od/fan_ctrl/fan_target_temperature"))
             .expect("Can't access fan_target_temperature file");
-        file.write_all(format!("{}\n", config.fan_target_temp.unwrap()).as_bytes())
+        write_sysfs(&mut file, format!("{}\n", fan_target_temp).as_bytes())
             .expect("Failed to write FAN_TARGET_TEMPERATURE");
     }
     // FAN_ZERO_RPM_ENABLE
-    if config.fan_zero_rpm.is_some() {
+    if let Some(fan_zero_rpm) = config.fan_zero_rpm {
         let file_result = OpenOptions::new().write(true)
             .open(config.home_path.join("gpu_od/fan_ctrl/fan_zero_rpm_enable"));
         if let Ok(mut file) = file_result {
-            file.write_all(format!("{}\n", config.fan_zero_rpm.unwrap()).as_bytes())
+            write_sysfs(&mut file, format!("{}\n", fan_zero_rpm).as_bytes())
                 .expect("Failed to write FAN_ZERO_RPM_ENABLE");
         } else {
             println!("Skip setting FAN_ZERO_RPM_ENABLE. Make sure to have Linux 6.13 or newer.");
         }
     }
     // FAN_ZERO_RPM_STOP_TEMPERATURE
-    if config.fan_zero_rpm_stop_temp.is_some() {
+    if let Some(fan_zero_rpm_stop_temp) = config.fan_zero_rpm_stop_temp {
         let file_result = OpenOptions::new().write(true)
             .open(config.home_path.join("gpu_od/fan_ctrl/fan_zero_rpm_stop_temperature"));
         if let Ok(mut file) = file_result {
-            file.write_all(format!("{}\n", config.fan_zero_rpm_stop_temp.unwrap()).as_bytes())
+            write_sysfs(&mut file, format!("{}\n", fan_zero_rpm_stop_temp).as_bytes())
                 .expect("Failed to write FAN_ZERO_RPM_STOP_TEMPERATURE");
         } else {
             println!("Skip setting FAN_ZERO_RPM_STOP_TEMPERATURE. Make sure to have Linux 6.13 or newer.");
         }
     }
+    // FAN_MINIMUM_PWM
+    if let Some(fan_minimum_pwm) = config.fan_minimum_pwm {
+        if config.home_path.join("gpu_od/fan_ctrl/fan_minimum_pwm").exists() {
+            let mut file = OpenOptions::new().write(true)
+                .open(config.home_path.join("gpu_od/fan_ctrl/fan_minimum_pwm"))
+                .expect("Can't access fan_minimum_pwm file");
+            write_sysfs(&mut file, format!("{}\n", fan_minimum_pwm).as_bytes())
+                .expect("Failed to write FAN_MINIMUM_PWM");
+        } else {
+            println!("Skip setting FAN_MINIMUM_PWM. Make sure the card and kernel support gpu_od/fan_ctrl/fan_minimum_pwm.");
+        }
+    }
+    // FAN_CURVE (Overrides the auto fan curve, so OD_ACOUSTIC_* and FAN_TARGET_TEMPERATURE no longer apply)
+    if !config.fan_curve.is_empty() {
+        let mut file = OpenOptions::new().write(true)
+            .open(config.home_path.join("gpu_od/fan_ctrl/fan_curve"))
+            .expect("Can't access fan_curve file");
+        for point in &config.fan_curve {
+            write_sysfs(&mut file, format!("{} {} {}\n", point.index, point.temp, point.pwm).as_bytes())
+                .expect("Failed to write FAN_CURVE point");
+        }
+        write_sysfs(&mut file, "c\n".as_bytes())
+            .expect("Failed to commit FAN_CURVE");
+    }
 
     // pp_od_clk_voltage
     let mut file = OpenOptions::new().write(true)
         .open(config.home_path.join("pp_od_clk_voltage"))
         .expect("Can't access pp_od_clk_voltage file");
 
-    // OD_SCLK_OFFSET (RDNA 4)
-    if config.od_sclk_offset.is_some() {
-        if config.od_sclk_offset.is_some() {
-            file.write_all(format!("s {}", config.od_sclk_offset.unwrap()).as_bytes())
-                .expect("Failed to write od_sclk_offset");
-        }
-    } else { // OD_SCLK (RDNA 3 or older)
-        if config.od_sclk_min.is_some() {
-            file.write_all(format!("s 0 {}", config.od_sclk_min.unwrap()).as_bytes())
-                .expect("Failed to write od_sclk_min");
-        }
-        if config.od_sclk_max.is_some() {
-            file.write_all(format!("s 1 {}", config.od_sclk_max.unwrap()).as_bytes())
-                .expect("Failed to write od_sclk_max");
-        }
+    // OD_SCLK_OFFSET (RDNA 4, capabilities::check makes sure only one of the two is set)
+    if let Some(od_sclk_offset) = config.od_sclk_offset {
+        write_sysfs(&mut file, format!("s {}", od_sclk_offset).as_bytes())
+            .expect("Failed to write od_sclk_offset");
     }
-    // OD_MCLK
-    if config.od_mclk_min.is_some() {
-        file.write_all(format!("m 0 {}", config.od_mclk_min.unwrap()).as_bytes())
-            .expect("Failed to write od_mclk_min");
+    // OD_SCLK (RDNA 3 or older)
+    for state in &config.od_sclk {
+        write_sysfs(&mut file, od::state_command('s', state).as_bytes())
+            .expect("Failed to write od_sclk");
     }
-    if config.od_mclk_max.is_some() {
-        file.write_all(format!("m 1 {}", config.od_mclk_max.unwrap()).as_bytes())
-            .expect("Failed to write od_mclk_max");
+    // OD_MCLK
+    for state in &config.od_mclk {
+        write_sysfs(&mut file, od::state_command('m', state).as_bytes())
+            .expect("Failed to write od_mclk");
     }
     // OD_VDDGFX_OFFSET
-    if config.od_vddgfx_offset.is_some() {
-        file.write_all(format!("vo {}", config.od_vddgfx_offset.unwrap()).as_bytes())
+    if let Some(od_vddgfx_offset) = config.od_vddgfx_offset {
+        write_sysfs(&mut file, format!("vo {}", od_vddgfx_offset).as_bytes())
             .expect("Failed to write od_vddgfx_offset");
     }
+    // OD_VDDC_CURVE (RDNA 1 and some RDNA 2)
+    for (point, sclk, voltage) in &config.od_vddc_curve {
+        write_sysfs(&mut file, format!("vc {} {} {}", point, sclk, voltage).as_bytes())
+            .expect("Failed to write od_vddc_curve");
+    }
     // NOTE: Commit to pp_od_clk_voltage (but it will actually just commit all "committable" settings on at least RDNA 3 or newer)
     // By "committable", see https://docs.kernel.org/gpu/amdgpu/thermal.html for all settings that require an explicit "c" to commit
-    file.write_all("c".as_bytes())
+    write_sysfs(&mut file, "c".as_bytes())
         .expect("Failed to commit final settings");
+
+    // DPM_* (After the commit since new OD settings rebuild the DPM levels)
+    if !config.dpm_masks.is_empty() && config.performance_level.as_deref().unwrap_or("manual") != "manual" {
+        println!("DPM_* settings need PERFORMANCE_LEVEL manual and will be ignored by the driver");
+    }
+    for (key, mask) in &config.dpm_masks {
+        let dpm_file = dpm::dpm_file(key).unwrap();
+        let mask: Vec<String> = mask.iter().map(u8::to_string).collect();
+        let mut file = OpenOptions::new().write(true)
+            .open(config.home_path.join(dpm_file))
+            .unwrap_or_else(|_| panic!("Can't access {} file", dpm_file));
+        write_sysfs(&mut file, mask.join(" ").as_bytes())
+            .unwrap_or_else(|_| panic!("Failed to write {}", key));
+    }
     println!("Success!");
 }
 
-fn reset_settings(path: &str) {
-    let file = File::open(path).expect("Profile not found");
-    let lines: Vec<String> = BufReader::new(file)
-        .lines()
-        .map(|l| l.expect("Can't parse line"))
-        .collect();
-    let id_str: &str = &lines[0];
-    let (id_type, id) = id_str.split_once(char::is_whitespace)
-        .expect("Error parsing CARD/UNIQUE_ID");
-    let mut config = DeviceConfig::default();
-    match id_type {
-        "CARD:" => config.card = Some(id.trim().parse().expect("Invalid CARD #")),
-        "UNIQUE_ID:" => config.device_id = Some(u64::from_str_radix(id.trim(), 16).expect("Invalid UNIQUE_ID #")),
-        _ => panic!("Unknown target device: Check /sys/class/drm"),
+// Every range check of the profile against the card, so an out of range value is refused before
+// reset_settings instead of leaving the card half configured
+fn validate_settings(config: &DeviceConfig) -> Result<(), String> {
+    // POWER_CAP
+    if let Some(power_cap) = config.power_cap {
+        let read_cap = |name: &str| fs::read_to_string(config.hwmon_path.join(name)).ok()?.trim().parse::<u64>().ok();
+        if let (Some(min), Some(max)) = (read_cap("power1_cap_min"), read_cap("power1_cap_max")) {
+            if power_cap < min || power_cap > max {
+                return Err(format!("POWER_CAP {} is out of range ({} - {})", power_cap, min, max));
+            }
+        }
+    }
+
+    // POWER_PROFILE_INDEX, POWER_PROFILE and POWER_PROFILE_CUSTOM
+    if config.power_profile_index.is_some() || config.power_profile.is_some() || !config.power_profile_custom.is_empty() {
+        let table = power_profile::read_power_profile_table(&config.home_path)
+            .ok_or("Can't access pp_power_profile_mode file")?;
+        if let Some(index) = config.power_profile_index {
+            if !table.modes.is_empty() && !table.modes.iter().any(|mode| mode.index == index) {
+                return Err(format!("Card has no power profile with POWER_PROFILE_INDEX {} (check pp_power_profile_mode)", index));
+            }
+        }
+        power_profile::custom_commands(&table, &config.power_profile_custom)?;
+        let name = match &config.power_profile {
+            Some(name) => name.as_str(),
+            None if config.power_profile_index.is_none() => power_profile::CUSTOM,
+            None => "",
+        };
+        if !name.is_empty() && table.find(name).is_none() {
+            return Err(format!("Card has no {} power profile (check pp_power_profile_mode)", name));
+        }
+    }
+
+    // FAN_MINIMUM_PWM and FAN_CURVE
+    if let Some(fan_minimum_pwm) = config.fan_minimum_pwm {
+        if let Some(current) = fan_ctrl::read_fan_ctrl_value(&config.home_path, "fan_minimum_pwm") {
+            fan_ctrl::validate_fan_ctrl_value("FAN_MINIMUM_PWM", fan_minimum_pwm, &current)?;
+        }
+    }
+    if !config.fan_curve.is_empty() {
+        let fan_curve = fan_ctrl::read_fan_curve(&config.home_path).ok_or("Can't access fan_curve file")?;
+        fan_ctrl::validate_fan_curve(&config.fan_curve, &fan_curve)?;
+    }
+
+    // pp_od_clk_voltage
+    let od_table = od::read_od_table(&config.home_path).ok_or("Can't access pp_od_clk_voltage file")?;
+    od_table.validate_states("OD_SCLK", "SCLK", &config.od_sclk)?;
+    od_table.validate_states("OD_MCLK", "MCLK", &config.od_mclk)?;
+    if !config.od_vddc_curve.is_empty() {
+        let points = od_table.section("OD_VDDC_CURVE")
+            .ok_or("Card has no OD_VDDC_CURVE (check pp_od_clk_voltage)")?;
+        for (point, sclk, voltage) in &config.od_vddc_curve {
+            if !points.entries.iter().any(|entry| entry.index == Some(*point)) {
+                return Err(format!("OD_VDDC_CURVE point {} does not exist", point));
+            }
+            od_table.validate("OD_VDDC_CURVE clock", &format!("VDDC_CURVE_SCLK[{}]", point), *sclk as i64)?;
+            od_table.validate("OD_VDDC_CURVE voltage", &format!("VDDC_CURVE_VOLT[{}]", point), *voltage as i64)?;
+        }
+    }
+
+    // DPM_*
+    for (key, mask) in &config.dpm_masks {
+        let dpm_file = dpm::dpm_file(key).unwrap();
+        let levels = dpm::read_dpm_levels(&config.home_path, dpm_file)
+            .ok_or(format!("Can't access {} file", dpm_file))?;
+        dpm::validate_dpm_mask(key, mask, &levels)?;
     }
+    Ok(())
+}
+
+fn reset_settings(path: &str) {
+    let lines = read_profile_lines(path);
+    let mut config = parse_profile_target(&lines);
     validate_detect_mount_points(&mut config);
+    state::clear_active_profile(&state::device_key(&config.home_path));
 
-    if config.card.is_some() {
-        println!("Resetting card {}...", config.card.unwrap());
-    } else if config.device_id.is_some() {
-        println!("Resetting device {:x}...", config.device_id.unwrap());
+    if let Some(card) = config.card {
+        println!("Resetting card {}...", card);
+    } else if let Some(device_id) = config.device_id {
+        println!("Resetting device {:x}...", device_id);
     }
 
     // Reset PERFORMANCE_LEVEL
     let mut file = OpenOptions::new().write(true)
         .open(config.home_path.join("power_dpm_force_performance_level"))
         .expect("Can't access power_dpm_force_performance_level file");
-    file.write_all("auto".as_bytes())
+    write_sysfs(&mut file, "auto".as_bytes())
         .expect("Failed to reset power_dpm_force_performance_level to \"auto\"");
 
     // Reset POWER_CAP
@@ -377,23 +767,36 @@ fn reset_settings(path: &str) {
     let mut file = OpenOptions::new().write(true)
         .open(config.hwmon_path.join("power1_cap"))
         .expect("Can't access power1_cap file");
-    file.write_all(power_cap_default.as_bytes())
+    write_sysfs(&mut file, power_cap_default.as_bytes())
         .expect("Failed to reset POWER_CAP");
 
-    // Reset POWER_PROFILE_INDEX
+    // Reset POWER_PROFILE_INDEX (BOOTUP_DEFAULT is not index 0 on every generation)
+    let bootup_default_index = power_profile::read_power_profile_table(&config.home_path)
+        .and_then(|table| table.find(power_profile::BOOTUP_DEFAULT).map(|mode| mode.index))
+        .unwrap_or(0);
     let mut file = OpenOptions::new().write(true)
         .open(config.home_path.join("pp_power_profile_mode"))
-        .expect("Can't access pp_od_clk_voltage file");
-    file.write_all("0".as_bytes()) // 0 is BOOTUP_DEFAULT
+        .expect("Can't access pp_power_profile_mode file");
+    write_sysfs(&mut file, bootup_default_index.to_string().as_bytes())
         .expect("Failed to reset power profile to BOOTUP_DEFAULT");
 
     // Reset pp_od_clk_voltage
     let mut file = OpenOptions::new().write(true)
         .open(config.home_path.join("pp_od_clk_voltage"))
         .expect("Can't access pp_od_clk_voltage file");
-    file.write_all("r".as_bytes())
+    write_sysfs(&mut file, "r".as_bytes())
         .expect("Failed to reset card with pp_od_clk_voltage_file");
 
+    // Reset FAN_MINIMUM_PWM (Not every kernel/card has it, and it is the only fan_ctrl file reset here)
+    let fan_minimum_pwm_path = config.home_path.join("gpu_od/fan_ctrl/fan_minimum_pwm");
+    if fan_minimum_pwm_path.exists() {
+        let mut file = OpenOptions::new().write(true)
+            .open(&fan_minimum_pwm_path)
+            .expect("Can't access fan_minimum_pwm file");
+        write_sysfs(&mut file, "r\n".as_bytes())
+            .expect("Failed to reset FAN_MINIMUM_PWM");
+    }
+
     // NOTE: AMDGPU driver also resets every settings that is "committable".
     // By "committable", see https://docs.kernel.org/gpu/amdgpu/thermal.html for all settings that require an explicit "c" to commit
 
@@ -401,29 +804,19 @@ fn reset_settings(path: &str) {
     println!("Success!");
 }
 
-fn read_card_settings(path: &str) {
-    let file = File::open(path).expect("Profile not found");
-    let lines: Vec<String> = BufReader::new(file)
-        .lines()
-        .map(|l| l.expect("Can't parse line"))
-        .collect();
-    let id_str: &str = &lines[0];
-    let (id_type, id) = id_str.split_once(char::is_whitespace)
-        .expect("Error parsing CARD/UNIQUE_ID");
-    let mut config = DeviceConfig::default();
-    match id_type {
-        "CARD:" => config.card = Some(id.trim().parse().expect("Invalid CARD #")),
-        "UNIQUE_ID:" => config.device_id = Some(u64::from_str_radix(id.trim(), 16).expect("Invalid UNIQUE_ID #")),
-        _ => panic!("Unknown target device: Check /sys/class/drm"),
-    }
+fn read_card_settings(path: &str, metrics: bool) {
+    let lines = read_profile_lines(path);
+    let mut config = parse_profile_target(&lines);
     validate_detect_mount_points(&mut config);
 
-    if config.card.is_some() {
-        println!("---------- Card {} Settings ----------", config.card.unwrap());
-    } else if config.device_id.is_some() {
+    if let Some(card) = config.card {
+        println!("---------- Card {} Settings ----------", card);
+    } else if let Some(device_id) = config.device_id {
         // TODO: Use pci-ids to get device name (Need to wait for pci-ids for subvendor entries)
-        println!("---------- Device {:x} Settings ----------", config.device_id.unwrap());
+        println!("---------- Device {:x} Settings ----------", device_id);
     }
+    capabilities::print_capabilities(&capabilities::probe(&config.home_path));
+    println!();
 
     // PERFORMANCE_LEVEL
     let file = File::open(config.home_path.join("power_dpm_force_performance_level"))
@@ -433,14 +826,11 @@ fn read_card_settings(path: &str) {
     }
 
     // POWER_PROFILE
-    let file = File::open(config.home_path.join("pp_power_profile_mode"))
+    let table = power_profile::read_power_profile_table(&config.home_path)
         .expect("Can't access pp_power_profile_mode");
-    println!("POWER_PROFILE_INDEX");
-    for line in BufReader::new(file).lines().map_while(Result::ok) {
-        if line.contains("*") {
-            println!("{}\n", line);
-        }
-    }
+    println!("POWER_PROFILE:");
+    power_profile::print_power_profile_table(&table);
+    println!();
 
     // POWER_CAP
     let file = File::open(config.hwmon_path.join("power1_cap"))
@@ -458,6 +848,22 @@ fn read_card_settings(path: &str) {
     }
     println!();
 
+    if let Some(od_table) = od::read_od_table(&config.home_path) {
+        if od_table.section("OD_VDDC_CURVE").is_some() {
+            od::print_vddc_curve(&od_table);
+            println!();
+        }
+    }
+
+    // PP_DPM_* (active level is marked with *)
+    for dpm_path in dpm::dpm_paths(&config.home_path) {
+        let dpm_file = dpm_path.file_name().unwrap().to_string_lossy().into_owned();
+        if let Some(levels) = dpm::read_dpm_levels(&config.home_path, &dpm_file) {
+            dpm::print_dpm_levels(&dpm_file, &levels);
+            println!();
+        }
+    }
+
     // FAN SETTINGS
     let fan_dir = config.home_path.join("gpu_od/fan_ctrl");
     let acoustic_limit_rpm_file = File::open(fan_dir.join("acoustic_limit_rpm_threshold"));
@@ -494,6 +900,102 @@ fn read_card_settings(path: &str) {
             println!("{}", line);
         }
     }
+    let fan_minimum_pwm_file = File::open(fan_dir.join("fan_minimum_pwm"));
+    if let Ok(fan_minimum_pwm_content) = fan_minimum_pwm_file {
+        println!();
+        for line in BufReader::new(fan_minimum_pwm_content).lines().map_while(Result::ok) {
+            println!("{}", line);
+        }
+    }
+    if let Some(fan_curve) = fan_ctrl::read_fan_curve(&config.home_path) {
+        println!();
+        fan_ctrl::print_fan_curve(&fan_curve);
+    }
+
+    // THROTTLE_STATUS (which limiter holds the clocks back) and GPU_METRICS (--metrics)
+    let gpu_metrics = gpu_metrics::read_gpu_metrics(&config.home_path);
+    if let Some(Ok(gpu_metrics)) = &gpu_metrics {
+        println!();
+        println!("THROTTLE_STATUS: {}", throttle::describe(gpu_metrics));
+    }
+    if metrics {
+        println!();
+        match gpu_metrics {
+            Some(Ok(gpu_metrics)) => gpu_metrics::print_gpu_metrics(&gpu_metrics),
+            Some(Err(e)) => println!("{}", e),
+            None => println!("Card has no gpu_metrics file"),
+        }
+    }
+}
+
+// Device of a profile, or a plain card number (commands that only read the card)
+fn resolve_target(profile: Option<&str>, card: Option<u8>) -> DeviceConfig {
+    let mut config = match card {
+        Some(card) => DeviceConfig { card: Some(card), ..Default::default() },
+        None => {
+            let lines = read_profile_lines(&(CONFIG_PROFILE_PATH.to_owned() + profile.unwrap_or("default")));
+            parse_profile_target(&lines)
+        },
+    };
+    validate_detect_mount_points(&mut config);
+    config
+}
+
+fn set_profile(profile: &str, wait: Option<Duration>) -> Result<(), String> {
+    let config_profile = CONFIG_PROFILE_PATH.to_owned() + profile;
+    if let Some(timeout) = wait {
+        wait_for_device(&config_profile, timeout)?;
+    }
+    apply_profile(profile, &config_profile, &read_profile_lines(&config_profile));
+    Ok(())
+}
+
+// Resets the card and applies the profile (lines may differ from the file, e.g. edits in the tui)
+fn apply_profile(profile: &str, path: &str, lines: &[String]) {
+    let config = parse_profile_lines(lines);
+    // Refuse the profile before touching the card
+    let capabilities = capabilities::probe(&config.home_path);
+    if let Err(e) = capabilities::check(&config, &capabilities).and_then(|_| validate_settings(&config)) {
+        panic!("{}", e);
+    }
+    reset_settings(path);
+    let device = state::device_key(&config.home_path);
+    apply_settings(profile, config);
+    state::save_active_profile(&device, profile);
+}
+
+// A missing or broken device must not keep the other devices from being restored
+fn restore_profile(profile: &str, wait: Option<Duration>) -> bool {
+    match panic::catch_unwind(AssertUnwindSafe(|| set_profile(profile, wait))) {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            eprintln!("Failed to restore profile {}: {}", profile, e);
+            false
+        },
+        Err(_) => {
+            eprintln!("Failed to restore profile {}", profile);
+            false
+        },
+    }
+}
+
+// Exits with 1 after trying every device if any of them failed
+fn restore_profiles(wait: Option<Duration>) {
+    let active_profiles = state::active_profiles();
+    let restored = if active_profiles.is_empty() {
+        println!("No active profile recorded. Restoring default profile...");
+        restore_profile("default", wait)
+    } else {
+        let mut restored = true;
+        for (device, profile) in active_profiles {
+            println!("Restoring profile {} on {}...", profile, device);
+            restored &= restore_profile(&profile, wait);
+        }
+        restored
+    };
+    if !restored {
+        std::process::exit(1);
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -511,42 +1013,255 @@ enum Commands {
         /// Device profile (card num in the profile) to read GPU info from
         #[arg(default_value_t=String::from("default"))]
         profile: String,
+        /// Seconds to wait for the device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+        /// Also decode gpu_metrics (temperatures, activity, clocks, power, ...)
+        #[arg(long)]
+        metrics: bool,
+        /// Output format (json and yaml are structured documents for scripts)
+        #[arg(long, value_enum, default_value_t = info::InfoFormat::Text)]
+        format: info::InfoFormat,
     },
     /// Set a device profile
     Set {
         /// Device profile
         #[arg(default_value_t=String::from("default"))]
         profile: String,
+        /// Seconds to wait for the device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+    },
+    /// Re-apply the last profile set on each device (or the default profile)
+    Restore {
+        /// Seconds to wait for each device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+    },
+    /// Entry points for systemd units and hooks
+    Hook {
+        #[command(subcommand)]
+        command: hook::HookCommands,
+    },
+    /// Drive the fan from the profile's SOFTWARE_FAN_CURVE (runs until stopped)
+    FanDaemon {
+        /// Device profile
+        #[arg(default_value_t=String::from("default"))]
+        profile: String,
+        /// Seconds to wait for the device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+    },
+    /// Watch the profile's ALERTs and FALLBACK_TRIGGERs (runs until stopped, fallback profiles need elevated privileges)
+    Watch {
+        /// Device profile
+        #[arg(default_value_t=String::from("default"))]
+        profile: String,
+        /// Seconds to wait for the device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+        /// Time between checks (e.g. 500ms, 1s)
+        #[arg(long, default_value = "1s", value_parser = log::parse_duration)]
+        interval: Duration,
+    },
+    /// Live sensor readout (no elevated privileges needed)
+    Monitor {
+        /// Device profile (defaults to the default profile)
+        profile: Option<String>,
+        /// Card number instead of a profile
+        #[arg(long, conflicts_with = "profile")]
+        card: Option<u8>,
+        /// Refresh interval
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Log the sensors to csv or json lines (no elevated privileges needed)
+    Log {
+        /// Device profile (defaults to the default profile)
+        profile: Option<String>,
+        /// Card number instead of a profile
+        #[arg(long, conflicts_with = "profile")]
+        card: Option<u8>,
+        /// Time between samples (e.g. 250ms, 1s)
+        #[arg(long, default_value = "1s", value_parser = log::parse_duration)]
+        interval: Duration,
+        /// Stop after this long (e.g. 10m, 1h)
+        #[arg(long, value_parser = log::parse_duration)]
+        duration: Option<Duration>,
+        #[arg(long, value_enum, default_value_t = log::LogFormat::Csv)]
+        format: log::LogFormat,
+        /// Log file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Rotate the log file when it reaches this size (e.g. 500K, 10M)
+        #[arg(long, value_parser = log::parse_size, requires = "output")]
+        rotate_size: Option<u64>,
+        /// Rotated log files to keep
+        #[arg(long, default_value_t = 5)]
+        keep: u32,
+    },
+    /// Record the energy used per profile and day (runs until stopped, saving needs elevated privileges)
+    Energy {
+        /// Device profile (defaults to the default profile)
+        profile: Option<String>,
+        /// Card number instead of a profile
+        #[arg(long, conflicts_with = "profile")]
+        card: Option<u8>,
+        /// Time between samples (e.g. 250ms, 1s)
+        #[arg(long, default_value = "1s", value_parser = log::parse_duration)]
+        interval: Duration,
+    },
+    /// Show the recorded energy (kWh, average and peak power) per profile and day
+    Stats {
+        /// Only show the last days
+        #[arg(long)]
+        days: Option<usize>,
+    },
+    /// Serve the sensors of every card as Prometheus metrics (no elevated privileges needed)
+    Exporter {
+        /// Address to serve /metrics on
+        #[arg(long, default_value = "127.0.0.1:9400")]
+        listen: String,
+        /// Write the metrics to a node_exporter textfile collector file instead (e.g. /var/lib/node_exporter/amdgpu.prom)
+        #[arg(long)]
+        textfile: Option<PathBuf>,
+        /// Keep rewriting the textfile this often (e.g. 15s) instead of writing it once
+        #[arg(long, value_parser = log::parse_duration, requires = "textfile")]
+        interval: Option<Duration>,
+    },
+    /// Interactive tuning with live graphs (apply and save need elevated privileges)
+    Tui {
+        /// Device profile
+        #[arg(default_value_t=String::from("default"))]
+        profile: String,
+    },
+    /// Generate systemd units (also runs as a systemd generator)
+    Systemd {
+        #[command(subcommand)]
+        command: systemd::SystemdCommands,
+    },
+    /// Generate udev rules
+    Udev {
+        #[command(subcommand)]
+        command: udev::UdevCommands,
+    },
+    /// Check the kernel version and amdgpu.ppfeaturemask (and the card of a profile)
+    Doctor {
+        /// Device profile to also check the card of
+        profile: Option<String>,
     },
     /// Reset a device
     Reset {
         /// Device profile (card num in the profile) to reset
         #[arg(default_value_t=String::from("default"))]
         profile: String,
+        /// Seconds to wait for the device to finish probing
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
     },
 }
 
 fn main() {
-    let args  = CliArgs::parse();
+    let args = match hook::systemd_sleep_args().or_else(systemd::systemd_generator_args) {
+        Some(hook_args) => CliArgs::parse_from(hook_args),
+        None => CliArgs::parse(),
+    };
+
+    // Commands touching the card explain the likely cause when they fail
+    if !matches!(args.command, Some(Commands::Doctor{..} | Commands::Systemd{..} | Commands::Udev{..})) {
+        doctor::report_on_panic();
+    }
 
     match args.command {
-        Some(Commands::Set{profile}) => {
+        Some(Commands::Set{profile, wait}) => {
             let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
-            let config = parse_profile(&config_profile);
-            reset_settings(&config_profile);
-            apply_settings(&profile, config);
+            if let Some(timeout) = wait {
+                wait_for_device_or_exit(&config_profile, Duration::from_secs(timeout));
+            }
+            set_profile(&profile, None).expect("Failed to set profile");
+        },
+        Some(Commands::Restore{wait}) => {
+            restore_profiles(wait.map(Duration::from_secs));
+        },
+        Some(Commands::Hook{command}) => {
+            hook::run(command);
+        },
+        Some(Commands::FanDaemon{profile, wait}) => {
+            let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
+            if let Some(timeout) = wait {
+                wait_for_device_or_exit(&config_profile, Duration::from_secs(timeout));
+            }
+            fan_daemon::run(&profile, parse_profile(&config_profile));
         },
-        Some(Commands::Reset{profile}) => {
+        Some(Commands::Watch{profile, wait, interval}) => {
             let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
+            if let Some(timeout) = wait {
+                wait_for_device_or_exit(&config_profile, Duration::from_secs(timeout));
+            }
+            alert::run(&profile, parse_profile(&config_profile), interval);
+        },
+        Some(Commands::Monitor{profile, card, interval}) => {
+            let config = resolve_target(profile.as_deref(), card);
+            monitor::run(&config, Duration::from_millis(interval));
+        },
+        Some(Commands::Log{profile, card, interval, duration, format, output, rotate_size, keep}) => {
+            let config = resolve_target(profile.as_deref(), card);
+            log::run(&config, log::LogOptions { interval, duration, format, output, rotate_size, keep });
+        },
+        Some(Commands::Energy{profile, card, interval}) => {
+            let config = resolve_target(profile.as_deref(), card);
+            energy::run(&config, interval);
+        },
+        Some(Commands::Stats{days}) => {
+            energy::print_stats(days);
+        },
+        Some(Commands::Exporter{listen, textfile, interval}) => {
+            exporter::run(exporter::ExporterOptions { listen, textfile, interval });
+        },
+        Some(Commands::Tui{profile}) => {
+            let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
+            let lines = read_profile_lines(&config_profile);
+            let mut config = parse_profile_target(&lines);
+            validate_detect_mount_points(&mut config);
+            tui::run(&profile, &config_profile, &config, lines);
+        },
+        Some(Commands::Systemd{command}) => {
+            systemd::run(command);
+        },
+        Some(Commands::Udev{command}) => {
+            udev::run(command);
+        },
+        Some(Commands::Doctor{profile}) => {
+            let home_path = profile.map(|profile| {
+                let lines = read_profile_lines(&(CONFIG_PROFILE_PATH.to_owned() + &profile));
+                let config = parse_profile_target(&lines);
+                find_card_path(&config).map(|path| path.join("device"))
+                    .expect("Fatal error: Unable to locate card mount point. Please check /sys/class/drm")
+            });
+            if !doctor::report(home_path.as_deref()) {
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Reset{profile, wait}) => {
+            let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
+            if let Some(timeout) = wait {
+                wait_for_device_or_exit(&config_profile, Duration::from_secs(timeout));
+            }
             reset_settings(&config_profile);
         },
-        Some(Commands::Info{profile}) => {
+        Some(Commands::Info{profile, wait, metrics, format}) => {
             let config_profile = CONFIG_PROFILE_PATH.to_owned() + &profile;
-            read_card_settings(&config_profile);
+            if let Some(timeout) = wait {
+                wait_for_device_or_exit(&config_profile, Duration::from_secs(timeout));
+            }
+            match format {
+                info::InfoFormat::Text => read_card_settings(&config_profile, metrics),
+                _ => info::print_info(&resolve_target(Some(&profile), None), format, metrics),
+            }
         },
         None => {
             let config_profile = CONFIG_PROFILE_PATH.to_owned() + "default";
-            read_card_settings(&config_profile);
+            read_card_settings(&config_profile, false);
         }
     };
 }
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Live sensor readout, one line per refresh (runs unprivileged)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::thread;
+use std::time::Duration;
+use crate::{sensors, throttle, DeviceConfig};
+
+fn push<T: std::fmt::Display>(line: &mut Vec<String>, name: &str, value: Option<T>, unit: &str) {
+    if let Some(value) = value {
+        line.push(format!("{} {}{}", name, value, unit));
+    }
+}
+
+pub fn format_sample(sample: &sensors::Sample) -> String {
+    let mut line: Vec<String> = Vec::new();
+    push(&mut line, "GPU", sample.gpu_busy.map(|v| format!("{:>3}", v)), "%");
+    push(&mut line, "MEM", sample.mem_busy.map(|v| format!("{:>3}", v)), "%");
+    push(&mut line, "SCLK", sample.sclk.map(|v| format!("{:>4}", v)), "Mhz");
+    push(&mut line, "MCLK", sample.mclk.map(|v| format!("{:>4}", v)), "Mhz");
+    push(&mut line, "EDGE", sample.temp_edge.map(|t| format!("{:>3.0}", t)), "C");
+    push(&mut line, "JUNCTION", sample.temp_junction.map(|t| format!("{:>3.0}", t)), "C");
+    push(&mut line, "MEM", sample.temp_mem.map(|t| format!("{:>3.0}", t)), "C");
+    push(&mut line, "FAN", sample.fan_rpm.map(|v| format!("{:>4}", v)), "RPM");
+    push(&mut line, "PWM", sample.fan_pwm.map(|v| format!("{:>3}", v)), "%");
+    // power1_average is missing on RDNA 3 or newer (power1_input instead)
+    push(&mut line, "POWER", sample.power_average.or(sample.power_input).map(|p| format!("{:>5.1}", p)), "W");
+    push(&mut line, "VDDGFX", sample.vddgfx.map(|v| format!("{:>4}", v)), "mV");
+    push(&mut line, "VDDNB", sample.vddnb.map(|v| format!("{:>4}", v)), "mV");
+    if let (Some(used), Some(total)) = (sample.vram_used, sample.vram_total) {
+        line.push(format!("VRAM {}/{}MiB", used >> 20, total >> 20));
+    }
+    if !sample.dpm_levels.is_empty() {
+        let levels: Vec<String> = sample.dpm_levels.iter()
+            .map(|(file, level)| format!("{}:{}", file.trim_start_matches("pp_dpm_"), level))
+            .collect();
+        line.push(format!("DPM {}", levels.join(" ")));
+    }
+    if let Some(metrics) = &sample.metrics {
+        line.push(format!("THROTTLE {}", throttle::short_description(metrics)));
+    }
+    line.join("  ")
+}
+
+pub fn run(config: &DeviceConfig, interval: Duration) {
+    loop {
+        let sample = sensors::read_sample(&config.home_path, Some(&config.hwmon_path));
+        println!("{}", format_sample(&sample));
+        thread::sleep(interval);
+    }
+}
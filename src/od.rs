@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Parsing of pp_od_clk_voltage (OD sections and OD_RANGE)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+// "0: 800Mhz 711mV" -> index 0, values [800, 711] (units are dropped)
+#[derive(Debug, Clone, Serialize)]
+pub struct OdEntry {
+    pub index: Option<u8>,
+    pub values: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OdSection {
+    pub name: String,
+    pub entries: Vec<OdEntry>,
+}
+
+// "VDDC_CURVE_SCLK[0]:     800Mhz       2150Mhz"
+#[derive(Debug, Clone, Serialize)]
+pub struct OdRange {
+    pub name: String,
+    pub min: i64,
+    pub max: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct OdTable {
+    pub sections: Vec<OdSection>,
+    pub ranges: Vec<OdRange>,
+}
+
+impl OdTable {
+    pub fn section(&self, name: &str) -> Option<&OdSection> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn range(&self, name: &str) -> Option<&OdRange> {
+        self.ranges.iter().find(|range| range.name == name)
+    }
+
+    // Checks a value against its OD_RANGE entry (values without an entry are not checked)
+    pub fn validate(&self, setting: &str, range_name: &str, value: i64) -> Result<(), String> {
+        match self.range(range_name) {
+            Some(range) if value < range.min || value > range.max => {
+                Err(format!("{} {} is out of range ({} {} - {})", setting, value, range_name, range.min, range.max))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // Checks OD_SCLK/OD_MCLK states against the states the card has and OD_RANGE
+    // (e.g. "SCLK: 300MHz 2000MHz" and "VDDC: 750mV 1200mV")
+    pub fn validate_states(&self, key: &str, range_name: &str, states: &[OdState]) -> Result<(), String> {
+        for (index, clock, voltage) in states {
+            if let Some(section) = self.section(key) {
+                let entry = section.entries.iter().find(|entry| entry.index == Some(*index))
+                    .ok_or(format!("{} state {} does not exist (card has {} states)", key, index, section.entries.len()))?;
+                if voltage.is_some() && entry.values.len() < 2 {
+                    return Err(format!("{} state {} has no voltage on this card", key, index));
+                }
+                // Vega and Polaris only take a state together with its voltage
+                if let (None, [_, current_voltage, ..]) = (voltage, &entry.values[..]) {
+                    return Err(format!("{} state {} needs a voltage on this card (e.g. \"{}: {}Mhz {}mV\")",
+                        key, index, index, clock, current_voltage));
+                }
+            }
+            self.validate(&format!("{} state {} clock", key, index), range_name, *clock as i64)?;
+            if let Some(voltage) = voltage {
+                self.validate(&format!("{} state {} voltage", key, index), "VDDC", *voltage as i64)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// One OD_SCLK/OD_MCLK state: (state, clock in Mhz, voltage in mV on Vega/Polaris)
+pub type OdState = (u8, u32, Option<u32>);
+
+fn parse_value(value: &str) -> Option<i64> {
+    value.trim_end_matches(|c: char| c.is_ascii_alphabetic()).parse().ok()
+}
+
+fn parse_values(values: &str) -> Vec<i64> {
+    values.split_whitespace().filter_map(parse_value).collect()
+}
+
+pub fn parse_od_table(content: &str) -> OdTable {
+    let mut table = OdTable::default();
+    let mut in_range = false;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        if line == "OD_RANGE:" {
+            in_range = true;
+        } else if let Some(name) = line.strip_suffix(':').filter(|name| !name.contains(char::is_whitespace)) {
+            in_range = false;
+            table.sections.push(OdSection { name: name.to_owned(), entries: Vec::new() });
+        } else if in_range {
+            if let Some((name, values)) = line.split_once(':') {
+                if let [min, max] = parse_values(values)[..] {
+                    table.ranges.push(OdRange { name: name.trim().to_owned(), min, max });
+                }
+            }
+        } else if let Some(section) = table.sections.last_mut() {
+            // "0: 800Mhz" or a plain value such as "-50mV" (OD_VDDGFX_OFFSET)
+            let entry = match line.split_once(':') {
+                Some((index, values)) => OdEntry { index: index.trim().parse().ok(), values: parse_values(values) },
+                None => OdEntry { index: None, values: parse_values(line) },
+            };
+            section.entries.push(entry);
+        }
+    }
+    table
+}
+
+// "1: 2500Mhz" or "7: 1600Mhz 1150mV"
+pub fn parse_od_state(line: &str) -> Option<OdState> {
+    let (index, values) = line.split_once(':')?;
+    let values = parse_values(values);
+    let (clock, voltage) = match values[..] {
+        [clock] => (clock, None),
+        [clock, voltage] => (clock, Some(u32::try_from(voltage).ok()?)),
+        _ => return None,
+    };
+    Some((index.trim().parse().ok()?, u32::try_from(clock).ok()?, voltage))
+}
+
+// "s 1 2500" or "s 7 1600 1150"
+pub fn state_command(command: char, (index, clock, voltage): &OdState) -> String {
+    match voltage {
+        Some(voltage) => format!("{} {} {} {}", command, index, clock, voltage),
+        None => format!("{} {} {}", command, index, clock),
+    }
+}
+
+pub fn read_od_table(home_path: &Path) -> Option<OdTable> {
+    let content = fs::read_to_string(home_path.join("pp_od_clk_voltage")).ok()?;
+    Some(parse_od_table(&content))
+}
+
+pub fn print_vddc_curve(table: &OdTable) {
+    let Some(section) = table.section("OD_VDDC_CURVE") else {
+        return;
+    };
+    println!("OD_VDDC_CURVE:");
+    println!("{:>5} {:>9} {:>8} {:>19} {:>17}", "POINT", "SCLK", "VOLTAGE", "SCLK RANGE", "VOLTAGE RANGE");
+    for entry in &section.entries {
+        let (Some(point), [sclk, voltage, ..]) = (entry.index, &entry.values[..]) else {
+            continue;
+        };
+        let sclk_range = table.range(&format!("VDDC_CURVE_SCLK[{}]", point))
+            .map(|r| format!("{}Mhz - {}Mhz", r.min, r.max))
+            .unwrap_or_default();
+        let voltage_range = table.range(&format!("VDDC_CURVE_VOLT[{}]", point))
+            .map(|r| format!("{}mV - {}mV", r.min, r.max))
+            .unwrap_or_default();
+        println!("{:>5} {:>6}Mhz {:>6}mV {:>19} {:>17}", point, sclk, voltage, sclk_range, voltage_range);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_need_a_voltage_on_vega() {
+        let table = parse_od_table("OD_SCLK:\n0:        852Mhz        800mV\n1:       1600Mhz       1150mV\nOD_RANGE:\nSCLK:     852MHz       2400MHz\nVDDC:     800mV        1200mV\n");
+        assert!(table.validate_states("OD_SCLK", "SCLK", &[(1, 1650, Some(1100))]).is_ok());
+        assert_eq!(table.validate_states("OD_SCLK", "SCLK", &[(1, 1650, None)]),
+            Err(String::from("OD_SCLK state 1 needs a voltage on this card (e.g. \"1: 1650Mhz 1150mV\")")));
+        assert!(table.validate_states("OD_SCLK", "SCLK", &[(1, 1650, Some(1300))]).is_err());
+        assert!(table.validate_states("OD_SCLK", "SCLK", &[(2, 1650, Some(1100))]).is_err());
+
+        // RDNA: min and max clock only
+        let table = parse_od_table("OD_SCLK:\n0: 500Mhz\n1: 2500Mhz\n");
+        assert!(table.validate_states("OD_SCLK", "SCLK", &[(1, 2600, None)]).is_ok());
+        assert!(table.validate_states("OD_SCLK", "SCLK", &[(1, 2600, Some(1100))]).is_err());
+    }
+}
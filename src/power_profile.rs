@@ -0,0 +1,450 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Parsing of pp_power_profile_mode and the CUSTOM power profile heuristics
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+pub const BOOTUP_DEFAULT: &str = "BOOTUP_DEFAULT";
+pub const CUSTOM: &str = "CUSTOM";
+const SMU7_FIRST_PARAMETER: &str = "SCLK_UP_HYST";
+
+// Heuristic parameters of one clock domain (name is empty on generations without per-clock rows)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClockHeuristics {
+    pub index: Option<u8>,
+    pub name: String,
+    pub values: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PowerProfileMode {
+    pub index: u8,
+    pub name: String,
+    pub active: bool,
+    pub clocks: Vec<ClockHeuristics>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PowerProfileTable {
+    pub parameters: Vec<String>,
+    pub modes: Vec<PowerProfileMode>,
+}
+
+// A CUSTOM profile line: "GFXCLK: 0 5 1 0 4 800 4587520 -65536 0" or just the values
+#[derive(Debug, Clone)]
+pub struct CustomHeuristics {
+    pub clock: Option<String>,
+    pub values: Vec<i64>,
+}
+
+impl PowerProfileTable {
+    pub fn find(&self, name: &str) -> Option<&PowerProfileMode> {
+        self.modes.iter().find(|mode| mode.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn parse_values(values: &str) -> Vec<i64> {
+    values.split_whitespace().map_while(|v| v.parse().ok()).collect()
+}
+
+// "0(       GFXCLK)       0       5       1 ..."
+fn parse_clock_row(line: &str) -> Option<ClockHeuristics> {
+    let (index, rest) = line.split_once('(')?;
+    let (name, values) = rest.split_once(')')?;
+    Some(ClockHeuristics {
+        index: Some(index.trim().parse().ok()?),
+        name: name.trim().to_owned(),
+        values: parse_values(values),
+    })
+}
+
+// "  0 BOOTUP_DEFAULT*:", "  1 3D_FULL_SCREEN :" or "  1 3D_FULL_SCREEN*:  70  60  1  3" (inline values)
+fn parse_mode_row(line: &str) -> Option<PowerProfileMode> {
+    let (index, rest) = line.trim().split_once(char::is_whitespace)?;
+    let index = index.parse().ok()?;
+    let (name, values) = match rest.split_once(':') {
+        Some((name, values)) => (name, values),
+        None => (rest, ""),
+    };
+    let active = name.contains('*');
+    let name = name.replace('*', "").trim().to_owned();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    let values = parse_values(values);
+    let clocks = if values.is_empty() {
+        Vec::new()
+    } else {
+        vec![ClockHeuristics { index: None, name: String::new(), values }]
+    };
+    Some(PowerProfileMode { index, name, active, clocks })
+}
+
+// Header: "PROFILE_INDEX(NAME) CLOCK_TYPE(NAME) FPS ...", "PROFILE_INDEX(NAME) BUSY_SET_POINT FPS ..."
+// or "NUM MODE_NAME SCLK_UP_HYST ..."
+fn parse_parameters(line: &str) -> Vec<String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let start = tokens.iter().position(|t| t.starts_with("CLOCK_TYPE"))
+        .or(tokens.iter().position(|t| *t == "MODE_NAME" || t.starts_with("PROFILE_INDEX")))
+        .map(|i| i + 1)
+        .unwrap_or(tokens.len());
+    tokens[start..].iter().map(|t| t.to_string()).collect()
+}
+
+// Header: "    0 BOOTUP_DEFAULT*  1 3D_FULL_SCREEN   2 POWER_SAVING ..." (indices are optional)
+fn parse_column_header(line: &str) -> Vec<PowerProfileMode> {
+    let mut modes: Vec<PowerProfileMode> = Vec::new();
+    let mut index: Option<u8> = None;
+    for token in line.split_whitespace() {
+        if let Ok(i) = token.parse() {
+            index = Some(i);
+        } else if token == "*" {
+            if let Some(mode) = modes.last_mut() {
+                mode.active = true;
+            }
+        } else {
+            modes.push(PowerProfileMode {
+                index: index.take().unwrap_or(modes.len() as u8),
+                name: token.trim_end_matches('*').to_owned(),
+                active: token.ends_with('*'),
+                clocks: Vec::new(),
+            });
+        }
+    }
+    modes
+}
+
+// "Gfx_FPS" -> ("GFXCLK", "FPS"), "Fclk_BoosterFreq" -> ("FCLK", "BoosterFreq")
+fn split_clock_parameter(label: &str) -> (String, &str) {
+    let (clock, parameter) = label.split_once('_').unwrap_or(("", label));
+    let clock = clock.to_uppercase();
+    if clock.ends_with("CLK") {
+        (clock, parameter)
+    } else {
+        (clock + "CLK", parameter)
+    }
+}
+
+// One column per profile and one row per heuristic parameter (SMU 13.0.7 / SMU 14). The rows are
+// prefixed with their clock (Gfx_, Fclk_), which becomes a clock row like on the other generations
+// since the CUSTOM profile is also written one clock at a time.
+fn parse_column_table(content: &str) -> PowerProfileTable {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let mut table = PowerProfileTable {
+        parameters: Vec::new(),
+        modes: lines.next().map(parse_column_header).unwrap_or_default(),
+    };
+    let columns = table.modes.len();
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() <= columns {
+            continue;
+        }
+        let (label, values) = tokens.split_at(tokens.len() - columns);
+        let values: Option<Vec<i64>> = values.iter().map(|v| v.parse().ok()).collect();
+        let Some(values) = values else {
+            continue;
+        };
+        let label = label.join(" ");
+        let (clock, parameter) = split_clock_parameter(&label);
+        for (mode, value) in table.modes.iter_mut().zip(values) {
+            if mode.clocks.last().is_none_or(|last| last.name != clock) {
+                let index = mode.clocks.len() as u8;
+                mode.clocks.push(ClockHeuristics { index: Some(index), name: clock.clone(), values: Vec::new() });
+            }
+            mode.clocks.last_mut().unwrap().values.push(value);
+        }
+        if table.modes.first().is_some_and(|mode| mode.clocks.len() == 1) {
+            table.parameters.push(parameter.to_owned());
+        }
+    }
+    table
+}
+
+// Every generation prints a different layout:
+// - One row per profile, optionally followed by one row per clock domain (SMU 11 / SMU 13.0.0)
+// - One row per profile with the values inline (Vega, Polaris)
+// - One column per profile (SMU 13.0.7 / SMU 14)
+// - A flat list of profiles (APUs)
+pub fn parse_power_profile_table(content: &str) -> PowerProfileTable {
+    let header = content.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    let header_names = header.split_whitespace()
+        .filter(|t| t.parse::<i64>().is_err() && *t != "*" && *t != ":")
+        .count();
+    if header.contains(BOOTUP_DEFAULT) && header_names > 1 {
+        return parse_column_table(content);
+    }
+
+    let mut table = PowerProfileTable::default();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            table.parameters = parse_parameters(trimmed);
+        } else if trimmed.contains('(') {
+            if let (Some(mode), Some(clock)) = (table.modes.last_mut(), parse_clock_row(trimmed)) {
+                mode.clocks.push(clock);
+            }
+        } else if let Some(mode) = parse_mode_row(trimmed) {
+            table.modes.push(mode);
+        }
+    }
+    table
+}
+
+pub fn read_power_profile_table(home_path: &Path) -> Option<PowerProfileTable> {
+    let content = fs::read_to_string(home_path.join("pp_power_profile_mode")).ok()?;
+    Some(parse_power_profile_table(&content))
+}
+
+// "GFXCLK: 0 5 1 ..." or "70 60 0 0"
+pub fn parse_custom_heuristics(line: &str) -> Option<CustomHeuristics> {
+    let (clock, values) = match line.split_once(':') {
+        Some((clock, values)) => (Some(clock.trim().to_owned()), values),
+        None => (None, line),
+    };
+    let values: Vec<i64> = values.split_whitespace().map(|v| v.parse().ok()).collect::<Option<_>>()?;
+    if values.is_empty() {
+        return None;
+    }
+    Some(CustomHeuristics { clock, values })
+}
+
+// Writes expected by pp_power_profile_mode to update the CUSTOM heuristics. Generations with per
+// clock rows or columns take "<custom index> <clock index> <values...>", the others "<custom index>
+// <values...>". The number of values must match what the card reports for the CUSTOM profile.
+pub fn custom_commands(table: &PowerProfileTable, custom: &[CustomHeuristics]) -> Result<Vec<String>, String> {
+    if custom.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mode = table.find(CUSTOM).ok_or("Card has no CUSTOM power profile")?;
+    let mut commands = Vec::new();
+    for heuristics in custom {
+        let (clock_index, expected) = match &heuristics.clock {
+            Some(name) => {
+                let clock = mode.clocks.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+                    .ok_or(format!("CUSTOM power profile has no {} clock", name))?;
+                (clock.index, clock.values.len())
+            },
+            // Polaris (smu7) also takes whether to update SCLK and MCLK: "<flag> <3 SCLK values> <flag> <3 MCLK values>"
+            None if table.parameters.first().is_some_and(|p| p == SMU7_FIRST_PARAMETER) => (None, table.parameters.len() + 2),
+            // Older generations print "-" for unset CUSTOM values, so fall back to the header
+            None if mode.clocks.iter().all(|c| c.index.is_none()) => {
+                (None, mode.clocks.first().map(|c| c.values.len()).unwrap_or(table.parameters.len()))
+            },
+            None => return Err(String::from("CUSTOM power profile needs a clock name (e.g. GFXCLK: ...)")),
+        };
+        if expected != heuristics.values.len() {
+            return Err(format!("POWER_PROFILE_CUSTOM {} needs {} values (got {})",
+                heuristics.clock.as_deref().unwrap_or(""), expected, heuristics.values.len()));
+        }
+        let mut command = mode.index.to_string();
+        if let Some(index) = clock_index {
+            command.push_str(&format!(" {}", index));
+        }
+        for value in &heuristics.values {
+            command.push_str(&format!(" {}", value));
+        }
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+pub fn print_power_profile_table(table: &PowerProfileTable) {
+    let name_width = table.modes.iter().map(|mode| mode.name.len()).max().unwrap_or(0) + 2;
+    let clock_width = table.modes.iter()
+        .flat_map(|mode| mode.clocks.iter().map(|clock| clock.name.len()))
+        .max()
+        .unwrap_or(0);
+    let value_widths: Vec<usize> = table.parameters.iter().enumerate().map(|(i, parameter)| {
+        table.modes.iter()
+            .flat_map(|mode| mode.clocks.iter().filter_map(|clock| clock.values.get(i)))
+            .map(|value| value.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max(parameter.len())
+    }).collect();
+
+    let mut header = format!("{:>5} {:<name_width$}", "INDEX", "NAME");
+    if clock_width > 0 {
+        header.push_str(&format!(" {:<clock_width$}", "CLOCK"));
+    }
+    for (parameter, width) in table.parameters.iter().zip(&value_widths) {
+        header.push_str(&format!(" {:>width$}", parameter));
+    }
+    println!("{}", header.trim_end());
+
+    for mode in &table.modes {
+        let name = format!("{}{}", mode.name, if mode.active { " *" } else { "" });
+        if mode.clocks.is_empty() {
+            println!("{:>5} {}", mode.index, name);
+        }
+        for (i, clock) in mode.clocks.iter().enumerate() {
+            let mut row = if i == 0 {
+                format!("{:>5} {:<name_width$}", mode.index, name)
+            } else {
+                format!("{:>5} {:<name_width$}", "", "")
+            };
+            if clock_width > 0 {
+                row.push_str(&format!(" {:<clock_width$}", clock.name));
+            }
+            for (value, width) in clock.values.iter().zip(&value_widths) {
+                row.push_str(&format!(" {:>width$}", value));
+            }
+            println!("{}", row.trim_end());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fixtures follow the sysfs_emit format strings of each generation's get_power_profile_mode
+    fn fixture(name: &str) -> PowerProfileTable {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/power_profile").join(name);
+        parse_power_profile_table(&fs::read_to_string(path).unwrap())
+    }
+
+    fn custom(line: &str) -> Vec<CustomHeuristics> {
+        vec![parse_custom_heuristics(line).unwrap()]
+    }
+
+    fn active(table: &PowerProfileTable) -> Vec<&str> {
+        table.modes.iter().filter(|mode| mode.active).map(|mode| mode.name.as_str()).collect()
+    }
+
+    fn clock_names(mode: &PowerProfileMode) -> Vec<&str> {
+        mode.clocks.iter().map(|clock| clock.name.as_str()).collect()
+    }
+
+    #[test]
+    fn polaris() {
+        let table = fixture("polaris.txt");
+        assert_eq!(table.parameters, ["SCLK_UP_HYST", "SCLK_DOWN_HYST", "SCLK_ACTIVE_LEVEL", "MCLK_UP_HYST", "MCLK_DOWN_HYST", "MCLK_ACTIVE_LEVEL"]);
+        assert_eq!(table.modes.len(), 7);
+        assert_eq!(active(&table), ["3D_FULL_SCREEN"]);
+        assert_eq!(table.find("3d_full_screen").unwrap().clocks[0].values, [0, 100, 30, 0, 100, 10]);
+        // "-" for the clocks a profile doesn't change
+        assert_eq!(table.find("POWER_SAVING").unwrap().clocks[0].values, [10, 0, 30]);
+        assert!(table.find(CUSTOM).unwrap().clocks.is_empty());
+        assert_eq!(table.find(CUSTOM).unwrap().index, 6);
+
+        // Whether to update SCLK, its 3 values, whether to update MCLK and its 3 values
+        assert_eq!(custom_commands(&table, &custom("1 0 100 30 1 0 100 10")), Ok(vec![String::from("6 1 0 100 30 1 0 100 10")]));
+        assert!(custom_commands(&table, &custom("0 100 30 0 100 10")).is_err());
+    }
+
+    #[test]
+    fn vega10() {
+        let table = fixture("vega10.txt");
+        assert_eq!(table.parameters, ["BUSY_SET_POINT", "FPS", "USE_RLC_BUSY", "MIN_ACTIVE_LEVEL"]);
+        assert_eq!(table.modes.len(), 7);
+        assert_eq!(active(&table), ["3D_FULL_SCREEN"]);
+        assert_eq!(table.find("COMPUTE").unwrap().clocks[0].values, [30, 60, 0, 6]);
+
+        assert_eq!(custom_commands(&table, &custom("70 60 1 3")), Ok(vec![String::from("6 70 60 1 3")]));
+        assert!(custom_commands(&table, &custom("70 60 1")).is_err());
+        assert!(custom_commands(&table, &custom("GFXCLK: 70 60 1 3")).is_err());
+    }
+
+    fn assert_smu11(table: &PowerProfileTable, memory_clock: &str) {
+        assert_eq!(table.parameters.len(), 9);
+        assert_eq!(table.parameters[0], "FPS");
+        assert_eq!(table.modes.len(), 7);
+        let mode = table.find(CUSTOM).unwrap();
+        assert_eq!(clock_names(mode), ["GFXCLK", "SOCCLK", memory_clock]);
+        assert_eq!(mode.clocks[2].index, Some(2));
+        assert_eq!(mode.clocks[0].values, [0, 5, 1, 0, 4, 800, 4587520, -65536, 0]);
+
+        assert_eq!(custom_commands(table, &custom("GFXCLK: 0 5 1 0 4 1000 4587520 -65536 0")),
+            Ok(vec![String::from("6 0 0 5 1 0 4 1000 4587520 -65536 0")]));
+        assert_eq!(custom_commands(table, &custom(&format!("{}: 0 1 0 0 1 0 327680 -6553 -65536", memory_clock.to_lowercase()))),
+            Ok(vec![String::from("6 2 0 1 0 0 1 0 327680 -6553 -65536")]));
+        // The clock name is needed and the number of values has to match
+        assert!(custom_commands(table, &custom("0 5 1 0 4 1000 4587520 -65536 0")).is_err());
+        assert!(custom_commands(table, &custom("GFXCLK: 0 5 1")).is_err());
+        assert!(custom_commands(table, &custom("FCLK: 0 5 1 0 4 1000 4587520 -65536 0")).is_err());
+    }
+
+    #[test]
+    fn navi10() {
+        let table = fixture("navi10.txt");
+        assert_eq!(active(&table), [BOOTUP_DEFAULT]);
+        assert_smu11(&table, "MEMLK");
+    }
+
+    #[test]
+    fn sienna_cichlid() {
+        let table = fixture("sienna_cichlid.txt");
+        assert_eq!(active(&table), ["3D_FULL_SCREEN"]);
+        assert_eq!(table.find("3D_FULL_SCREEN").unwrap().clocks[0].values[5], 900);
+        assert_smu11(&table, "MEMCLK");
+    }
+
+    #[test]
+    fn smu_v13_0_0() {
+        let table = fixture("smu_v13_0_0.txt");
+        assert_eq!(table.parameters.len(), 8);
+        assert_eq!(table.modes.len(), 8);
+        assert_eq!(table.find("WINDOW_3D").unwrap().index, 7);
+        assert_eq!(active(&table), [BOOTUP_DEFAULT]);
+        assert_eq!(clock_names(table.find(CUSTOM).unwrap()), ["GFXCLK", "FCLK"]);
+        assert_eq!(custom_commands(&table, &custom("FCLK: 0 1 0 1 0 3276800 -6553 -65536")),
+            Ok(vec![String::from("6 1 0 1 0 1 0 3276800 -6553 -65536")]));
+    }
+
+    fn assert_column_table(table: &PowerProfileTable) {
+        assert_eq!(table.parameters, ["ActiveHystLimit", "IdleHystLimit", "FPS", "MinActiveFreqType", "BoosterFreqType", "MinActiveFreq", "BoosterFreq"]);
+        let names: Vec<&str> = table.modes.iter().map(|mode| mode.name.as_str()).collect();
+        assert_eq!(names, [BOOTUP_DEFAULT, "3D_FULL_SCREEN", "POWER_SAVING", "VIDEO", "VR", "COMPUTE", CUSTOM, "WINDOW_3D"]);
+        assert!(table.modes.iter().enumerate().all(|(i, mode)| mode.index as usize == i));
+        let mode = table.find("3D_FULL_SCREEN").unwrap();
+        assert_eq!(clock_names(mode), ["GFXCLK", "FCLK"]);
+        assert_eq!(mode.clocks[0].values[2], 21); // Gfx_FPS
+        assert_eq!(mode.clocks[1].values, [71, 81, 91, 101, 111, 121, 131]); // Fclk_*
+        assert_eq!(table.find(CUSTOM).unwrap().clocks[1].values, [0; 7]);
+
+        // One write per clock: the clock index and its 7 values
+        let mut heuristics = custom("GFXCLK: 1 2 3 4 5 6 7");
+        heuristics.extend(custom("FCLK: 8 9 10 11 12 13 14"));
+        assert_eq!(custom_commands(table, &heuristics),
+            Ok(vec![String::from("6 0 1 2 3 4 5 6 7"), String::from("6 1 8 9 10 11 12 13 14")]));
+        assert!(custom_commands(table, &custom("1 2 3 4 5 6 7 8 9 10 11 12 13 14")).is_err());
+        assert!(custom_commands(table, &custom("GFXCLK: 1 2 3 4 5 6 7 8 9 10 11 12 13 14")).is_err());
+    }
+
+    #[test]
+    fn smu_v13_0_7() {
+        let table = fixture("smu_v13_0_7.txt");
+        assert_column_table(&table);
+        // "VIDEO         * " (the marker is its own token after a short name)
+        assert_eq!(active(&table), ["VIDEO"]);
+    }
+
+    #[test]
+    fn smu_v14_0_2() {
+        let table = fixture("smu_v14_0_2.txt");
+        assert_column_table(&table);
+        assert_eq!(active(&table), ["3D_FULL_SCREEN"]);
+    }
+
+    #[test]
+    fn custom_commands_without_custom() {
+        let table = fixture("vega10.txt");
+        assert_eq!(custom_commands(&table, &[]), Ok(Vec::new()));
+        let table = parse_power_profile_table(" 0 BOOTUP_DEFAULT*\n 1 3D_FULL_SCREEN \n 2   POWER_SAVING \n");
+        assert_eq!(table.modes.len(), 3);
+        assert!(custom_commands(&table, &custom("70 60 1 3")).is_err());
+        assert!(parse_custom_heuristics("GFXCLK: 1 two 3").is_none());
+        assert!(parse_custom_heuristics("GFXCLK:").is_none());
+    }
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Reading of the live sensors (device attributes, hwmon and gpu_metrics). Everything here is
+ * readable without elevated privileges.
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::Path;
+use crate::dpm;
+use crate::gpu_metrics::{self, GpuMetrics};
+
+// One reading of every sensor (None if the card or kernel doesn't have it)
+#[derive(Debug, Default)]
+pub struct Sample {
+    pub gpu_busy: Option<u32>, // Percent
+    pub mem_busy: Option<u32>, // Percent
+    pub sclk: Option<u64>, // Mhz
+    pub mclk: Option<u64>, // Mhz
+    pub temp_edge: Option<f64>, // Celsius
+    pub temp_junction: Option<f64>,
+    pub temp_mem: Option<f64>,
+    pub fan_rpm: Option<u64>,
+    pub fan_pwm: Option<u64>, // Percent
+    pub power_average: Option<f64>, // W
+    pub power_input: Option<f64>, // W
+    pub power_cap: Option<f64>, // W
+    pub vddgfx: Option<u64>, // mV
+    pub vddnb: Option<u64>, // mV
+    pub vram_used: Option<u64>, // Bytes
+    pub vram_total: Option<u64>,
+    pub dpm_levels: Vec<(String, u8)>, // (pp_dpm_* file, active level)
+    pub metrics: Option<GpuMetrics>,
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// Without a hwmon directory only the device files and gpu_metrics are read
+pub fn read_sample(home_path: &Path, hwmon_path: Option<&Path>) -> Sample {
+    let hwmon = |name: &str| read_u64(&hwmon_path?.join(name));
+    let device = |name: &str| read_u64(&home_path.join(name));
+
+    let dpm_levels = dpm::dpm_paths(home_path).iter().filter_map(|path| {
+        let file = path.file_name()?.to_string_lossy().into_owned();
+        let levels = dpm::read_dpm_levels(home_path, &file)?;
+        let active = levels.iter().find(|level| level.active)?;
+        Some((file, active.index))
+    }).collect();
+
+    // gpu_metrics fills in what hwmon doesn't have (e.g. freq2_input on APUs)
+    let metrics = gpu_metrics::read_gpu_metrics(home_path).and_then(Result::ok);
+    let from_metrics = |value: fn(&GpuMetrics) -> Option<u16>| metrics.as_ref().and_then(value).map(u64::from);
+
+    Sample {
+        gpu_busy: device("gpu_busy_percent").map(|v| v as u32),
+        mem_busy: device("mem_busy_percent").map(|v| v as u32),
+        sclk: hwmon("freq1_input").map(|hz| hz / 1_000_000).or(from_metrics(|m| m.current_gfxclk)),
+        mclk: hwmon("freq2_input").map(|hz| hz / 1_000_000).or(from_metrics(|m| m.current_uclk)),
+        temp_edge: hwmon("temp1_input").map(|t| t as f64 / 1000.0),
+        temp_junction: hwmon("temp2_input").map(|t| t as f64 / 1000.0),
+        temp_mem: hwmon("temp3_input").map(|t| t as f64 / 1000.0),
+        fan_rpm: hwmon("fan1_input").or(from_metrics(|m| m.current_fan_speed)),
+        fan_pwm: hwmon("pwm1").map(|pwm| pwm * 100 / 255),
+        power_average: hwmon("power1_average").map(|uw| uw as f64 / 1_000_000.0),
+        power_input: hwmon("power1_input").map(|uw| uw as f64 / 1_000_000.0),
+        power_cap: hwmon("power1_cap").map(|uw| uw as f64 / 1_000_000.0),
+        vddgfx: hwmon("in0_input"),
+        vddnb: hwmon("in1_input"),
+        vram_used: device("mem_info_vram_used"),
+        vram_total: device("mem_info_vram_total"),
+        dpm_levels,
+        metrics,
+    }
+}
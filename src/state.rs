@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Remember which profile is applied to each device
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// NOTE: Lives under /run so a fresh boot always starts from the default profile
+const STATE_PATH: &str = "/run/amdgpu-settings";
+
+// Key a device by its PCI slot (e.g. 0000:03:00.0) so CARD and UNIQUE_ID profiles of the
+// same GPU share one entry
+pub fn device_key(home_path: &Path) -> String {
+    let device_path = fs::canonicalize(home_path).unwrap_or(home_path.to_path_buf());
+    device_path.file_name()
+        .expect("Can't determine device PCI slot")
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn state_file(device: &str) -> PathBuf {
+    PathBuf::from(STATE_PATH).join(device)
+}
+
+pub fn save_active_profile(device: &str, profile: &str) {
+    let result = fs::create_dir_all(STATE_PATH)
+        .and_then(|_| fs::write(state_file(device), format!("{}\n", profile)));
+    if let Err(e) = result {
+        println!("Unable to save active profile for {}: {}", device, e);
+    }
+}
+
+pub fn clear_active_profile(device: &str) {
+    // Nothing to clear if the device was never set
+    let _ = fs::remove_file(state_file(device));
+}
+
+pub fn active_profile(device: &str) -> Option<String> {
+    let profile = fs::read_to_string(state_file(device)).ok()?;
+    let profile = profile.trim();
+    (!profile.is_empty()).then(|| profile.to_owned())
+}
+
+// Returns (device, profile) pairs of every recorded device
+pub fn active_profiles() -> Vec<(String, String)> {
+    let mut profiles = Vec::new();
+    let Ok(entries) = fs::read_dir(STATE_PATH) else {
+        return profiles;
+    };
+    for entry in entries.flatten() {
+        if let Ok(profile) = fs::read_to_string(entry.path()) {
+            let profile = profile.trim();
+            if !profile.is_empty() {
+                profiles.push((entry.file_name().to_string_lossy().into_owned(), profile.to_owned()));
+            }
+        }
+    }
+    profiles.sort();
+    profiles
+}
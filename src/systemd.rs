@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * systemd unit generator (one templated unit instance per profile)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use clap::Subcommand;
+
+const SYSTEM_GENERATORS_DIR: &str = "system-generators";
+const TEMPLATE_UNIT: &str = "amdgpu-settings@.service";
+const WATCH_TEMPLATE_UNIT: &str = "amdgpu-settings-watch@.service";
+const DEFAULT_BIN_PATH: &str = "/usr/local/bin/amdgpu-settings";
+
+#[derive(Subcommand, Debug)]
+pub enum SystemdCommands {
+    /// Write amdgpu-settings@.service and a drop-in for every profile
+    Generate {
+        /// Output directory (systemd passes its normal generator directory here)
+        normal_dir: PathBuf,
+        /// Ignored (part of the systemd generator calling convention)
+        early_dir: Option<PathBuf>,
+        /// Ignored (part of the systemd generator calling convention)
+        late_dir: Option<PathBuf>,
+    },
+}
+
+struct ProfileUnit {
+    profile: String,
+    device_unit: Option<String>,
+    watch: bool, // Has ALERT or FALLBACK_PROFILE
+}
+
+// systemd runs every executable in system-generators/ as `<generator> normal early late`, so a
+// symlink to this binary there is turned into `systemd generate normal early late`
+pub fn systemd_generator_args() -> Option<Vec<String>> {
+    let mut args = std::env::args();
+    let argv0 = PathBuf::from(args.next()?);
+    let parent = argv0.parent()?.file_name()?;
+    if parent != SYSTEM_GENERATORS_DIR {
+        return None;
+    }
+    let mut generator_args = vec![
+        argv0.to_string_lossy().into_owned(),
+        String::from("systemd"),
+        String::from("generate"),
+    ];
+    generator_args.extend(args);
+    Some(generator_args)
+}
+
+// Same rules as `systemd-escape`
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for (i, c) in value.bytes().enumerate() {
+        match c {
+            b'/' => escaped.push('-'),
+            b'.' if i == 0 => escaped.push_str("\\x2e"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => escaped.push(c as char),
+            _ => escaped.push_str(&format!("\\x{:02x}", c)),
+        }
+    }
+    escaped
+}
+
+// Same rules as `systemd-escape --path`
+fn escape_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        return String::from("-");
+    }
+    escape(trimmed)
+}
+
+pub fn instance_unit(profile: &str) -> String {
+    format!("amdgpu-settings@{}.service", escape(profile))
+}
+
+fn watch_instance_unit(profile: &str) -> String {
+    format!("amdgpu-settings-watch@{}.service", escape(profile))
+}
+
+fn has_watch_keys(profile: &str) -> bool {
+    crate::read_profile_lines(&(crate::CONFIG_PROFILE_PATH.to_owned() + profile)).iter()
+        .any(|line| matches!(line.trim(), "ALERT:" | "FALLBACK_PROFILE:"))
+}
+
+// Prefer the /sys/devices path of the card. Before amdgpu has probed (early boot) only CARD
+// profiles can be bound, through the /dev/dri/card# device unit.
+fn device_unit(profile: &str) -> Option<String> {
+    let lines = crate::read_profile_lines(&(crate::CONFIG_PROFILE_PATH.to_owned() + profile));
+    let config = crate::parse_profile_target(&lines);
+    if let Some(card_path) = crate::find_card_path(&config) {
+        if let Ok(device_path) = fs::canonicalize(card_path) {
+            return Some(format!("{}.device", escape_path(&device_path)));
+        }
+    }
+    config.card.map(|card| format!("dev-dri-card{}.device", card))
+}
+
+fn template_unit(bin_path: &str) -> String {
+    format!("\
+# Generated by amdgpu-settings
+[Unit]
+Description=Apply amdgpu-settings profile %I
+Wants=modprobe@amdgpu.service
+After=modprobe@amdgpu.service systemd-user-sessions.service
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+ExecStart={bin} set --wait 30 %I
+ExecStop={bin} reset %I
+ExecReload={bin} set --wait 30 %I
+", bin = bin_path)
+}
+
+// Stopped with its profile (PartOf), e.g. when another profile of the same GPU is started
+fn watch_template_unit(bin_path: &str) -> String {
+    format!("\
+# Generated by amdgpu-settings
+[Unit]
+Description=Watch the alerts of amdgpu-settings profile %I
+PartOf=amdgpu-settings@%i.service
+After=amdgpu-settings@%i.service
+
+[Service]
+ExecStart={bin} watch --wait 30 %I
+Restart=on-failure
+", bin = bin_path)
+}
+
+fn device_dropin(unit: &ProfileUnit, units: &[ProfileUnit]) -> String {
+    let mut dropin = format!("\
+# Generated by amdgpu-settings from {}{}
+[Unit]
+", crate::CONFIG_PROFILE_PATH, unit.profile);
+    if let Some(device_unit) = &unit.device_unit {
+        dropin.push_str(&format!("BindsTo={}\nAfter={}\n", device_unit, device_unit));
+        // Only one profile can be active on a device at a time
+        for other in units {
+            if other.profile != unit.profile && other.device_unit.as_ref() == Some(device_unit) {
+                dropin.push_str(&format!("Conflicts={}\n", instance_unit(&other.profile)));
+            }
+        }
+    }
+    if unit.watch {
+        dropin.push_str(&format!("Wants={}\n", watch_instance_unit(&unit.profile)));
+    }
+    dropin
+}
+
+fn generate(normal_dir: &Path) {
+    let bin_path = std::env::current_exe()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or(String::from(DEFAULT_BIN_PATH));
+
+    fs::create_dir_all(normal_dir).expect("Can't create unit directory");
+    fs::write(normal_dir.join(TEMPLATE_UNIT), template_unit(&bin_path))
+        .expect("Failed to write amdgpu-settings@.service");
+    println!("{}", normal_dir.join(TEMPLATE_UNIT).display());
+    fs::write(normal_dir.join(WATCH_TEMPLATE_UNIT), watch_template_unit(&bin_path))
+        .expect("Failed to write amdgpu-settings-watch@.service");
+    println!("{}", normal_dir.join(WATCH_TEMPLATE_UNIT).display());
+
+    let units: Vec<ProfileUnit> = crate::list_profiles().into_iter()
+        .map(|profile| ProfileUnit { device_unit: device_unit(&profile), watch: has_watch_keys(&profile), profile })
+        .collect();
+    for unit in &units {
+        let dropin_dir = normal_dir.join(format!("{}.d", instance_unit(&unit.profile)));
+        fs::create_dir_all(&dropin_dir).expect("Can't create drop-in directory");
+        fs::write(dropin_dir.join("10-device.conf"), device_dropin(unit, &units))
+            .expect("Failed to write drop-in");
+        println!("{}", dropin_dir.join("10-device.conf").display());
+    }
+
+    // Start the default profile at boot (same as the shipped amdgpu-settings.service)
+    if units.iter().any(|unit| unit.profile == "default") {
+        let wants_dir = normal_dir.join("multi-user.target.wants");
+        let wants_link = wants_dir.join(instance_unit("default"));
+        fs::create_dir_all(&wants_dir).expect("Can't create multi-user.target.wants");
+        let _ = fs::remove_file(&wants_link);
+        symlink(Path::new("..").join(TEMPLATE_UNIT), &wants_link)
+            .expect("Failed to enable amdgpu-settings@default.service");
+        println!("{}", wants_link.display());
+    }
+}
+
+pub fn run(command: SystemdCommands) {
+    match command {
+        SystemdCommands::Generate{normal_dir, ..} => generate(&normal_dir),
+    }
+}
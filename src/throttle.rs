@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Decoding of the gpu_metrics throttle status bits (which limiters hold the clocks back)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use crate::gpu_metrics::GpuMetrics;
+
+// (bit, limiter, what it means)
+type Limiter = (u8, &'static str, &'static str);
+
+// indep_throttle_status (SMU_THROTTLER_* in amdgpu_smu.h, same on every ASIC)
+const INDEP_LIMITERS: [Limiter; 34] = [
+    (0, "PPT0", "power limit (POWER_CAP)"),
+    (1, "PPT1", "power limit"),
+    (2, "PPT2", "power limit"),
+    (3, "PPT3", "power limit"),
+    (4, "SPL", "sustained power limit"),
+    (5, "FPPT", "fast power limit"),
+    (6, "SPPT", "slow power limit"),
+    (7, "SPPT_APU", "slow APU power limit"),
+    (16, "TDC_GFX", "GFX current limit"),
+    (17, "TDC_SOC", "SOC current limit"),
+    (18, "TDC_MEM", "memory current limit"),
+    (19, "TDC_VDD", "VDD current limit"),
+    (20, "TDC_CVIP", "CVIP current limit"),
+    (21, "EDC_CPU", "CPU current limit"),
+    (22, "EDC_GFX", "GFX current limit"),
+    (23, "APCC", "APCC current limit"),
+    (32, "TEMP_GPU", "GPU temperature"),
+    (33, "TEMP_CORE", "CPU core temperature"),
+    (34, "TEMP_MEM", "memory temperature"),
+    (35, "TEMP_EDGE", "edge temperature"),
+    (36, "TEMP_HOTSPOT", "junction temperature"),
+    (37, "TEMP_SOC", "SOC temperature"),
+    (38, "TEMP_VR_GFX", "GFX VR temperature"),
+    (39, "TEMP_VR_SOC", "SOC VR temperature"),
+    (40, "TEMP_VR_MEM0", "memory VR temperature"),
+    (41, "TEMP_VR_MEM1", "memory VR temperature"),
+    (42, "TEMP_LIQUID0", "liquid temperature"),
+    (43, "TEMP_LIQUID1", "liquid temperature"),
+    (44, "VRHOT0", "VR hot"),
+    (45, "VRHOT1", "VR hot"),
+    (46, "PROCHOT_CPU", "CPU PROCHOT"),
+    (47, "PROCHOT_GFX", "GFX PROCHOT"),
+    (56, "PPM", "platform power management"),
+    (57, "FIT", "reliability (FIT) limit"),
+];
+
+// throttle_status of gpu_metrics v1_0 (Navi 1x, THROTTLER_* in smu11_driver_if_navi10.h). Newer
+// revisions report indep_throttle_status and their throttle_status bits differ per ASIC.
+const NAVI10_LIMITERS: [Limiter; 20] = [
+    (0, "TEMP_EDGE", "edge temperature"),
+    (1, "TEMP_HOTSPOT", "junction temperature"),
+    (2, "TEMP_MEM", "memory temperature"),
+    (3, "TEMP_VR_GFX", "GFX VR temperature"),
+    (4, "TEMP_VR_MEM0", "memory VR temperature"),
+    (5, "TEMP_VR_MEM1", "memory VR temperature"),
+    (6, "TEMP_VR_SOC", "SOC VR temperature"),
+    (7, "TEMP_LIQUID0", "liquid temperature"),
+    (8, "TEMP_LIQUID1", "liquid temperature"),
+    (9, "TEMP_PLX", "PLX temperature"),
+    (10, "TEMP_SKIN", "skin temperature"),
+    (11, "TDC_GFX", "GFX current limit"),
+    (12, "TDC_SOC", "SOC current limit"),
+    (13, "PPT0", "power limit (POWER_CAP)"),
+    (14, "PPT1", "power limit"),
+    (15, "PPT2", "power limit"),
+    (16, "PPT3", "power limit"),
+    (17, "FIT", "reliability (FIT) limit"),
+    (18, "PPM", "platform power management"),
+    (19, "APCC", "APCC current limit"),
+];
+
+fn decode(status: u64, limiters: &[Limiter]) -> Vec<(&'static str, &'static str)> {
+    limiters.iter()
+        .filter(|(bit, _, _)| status & (1 << bit) != 0)
+        .map(|(_, name, description)| (*name, *description))
+        .collect()
+}
+
+// Every limiter the card's throttle bits can report (empty if they can't be decoded)
+pub fn limiters(metrics: &GpuMetrics) -> Vec<&'static str> {
+    let limiters: &[Limiter] = match (metrics.indep_throttle_status, metrics.format_revision, metrics.content_revision, metrics.throttle_status) {
+        (Some(_), _, _, _) => &INDEP_LIMITERS,
+        (None, 1, 0, Some(_)) => &NAVI10_LIMITERS,
+        _ => &[],
+    };
+    limiters.iter().map(|(_, name, _)| *name).collect()
+}
+
+// Active limiters, or None if the card doesn't report throttle bits this tool can decode
+pub fn active_limiters(metrics: &GpuMetrics) -> Option<Vec<(&'static str, &'static str)>> {
+    if let Some(status) = metrics.indep_throttle_status {
+        return Some(decode(status, &INDEP_LIMITERS));
+    }
+    match (metrics.format_revision, metrics.content_revision, metrics.throttle_status) {
+        (1, 0, Some(status)) => Some(decode(u64::from(status), &NAVI10_LIMITERS)),
+        _ => None,
+    }
+}
+
+// "PPT0 (power limit (POWER_CAP)), TEMP_HOTSPOT (junction temperature)" or "none"
+pub fn describe(metrics: &GpuMetrics) -> String {
+    match active_limiters(metrics) {
+        Some(limiters) if limiters.is_empty() => String::from("none"),
+        Some(limiters) => limiters.iter()
+            .map(|(name, description)| format!("{} ({})", name, description))
+            .collect::<Vec<String>>()
+            .join(", "),
+        None => match metrics.throttle_status {
+            Some(status) => format!("{:#010x} (ASIC specific bits, not decoded)", status),
+            None => String::from("unknown"),
+        },
+    }
+}
+
+// Short form for the monitor: "PPT0 TEMP_HOTSPOT"
+pub fn short_description(metrics: &GpuMetrics) -> String {
+    match active_limiters(metrics) {
+        Some(limiters) if limiters.is_empty() => String::from("-"),
+        Some(limiters) => limiters.iter().map(|(name, _)| *name).collect::<Vec<&str>>().join(" "),
+        None => String::from("?"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(limiters: Option<Vec<(&'static str, &'static str)>>) -> Vec<&'static str> {
+        limiters.unwrap().iter().map(|(name, _)| *name).collect()
+    }
+
+    #[test]
+    fn navi10_throttle_status() {
+        // THROTTLER_TEMP_SKIN_BIT, THROTTLER_PPT0_BIT and THROTTLER_APCC_BIT
+        let metrics = GpuMetrics { format_revision: 1, content_revision: 0, throttle_status: Some(0x8_2400), ..Default::default() };
+        assert_eq!(names(active_limiters(&metrics)), ["TEMP_SKIN", "PPT0", "APCC"]);
+        assert_eq!(limiters(&metrics).len(), 20);
+
+        let metrics = GpuMetrics { throttle_status: Some(1 << 11), ..metrics };
+        assert_eq!(names(active_limiters(&metrics)), ["TDC_GFX"]);
+    }
+
+    #[test]
+    fn indep_throttle_status() {
+        let metrics = GpuMetrics {
+            format_revision: 1,
+            content_revision: 3,
+            throttle_status: Some(0x2400), // ASIC specific, ignored
+            indep_throttle_status: Some(1 << 36 | 1 << 16 | 1),
+            ..Default::default()
+        };
+        assert_eq!(names(active_limiters(&metrics)), ["PPT0", "TDC_GFX", "TEMP_HOTSPOT"]);
+        // Other revisions only have the ASIC specific bits
+        let metrics = GpuMetrics { indep_throttle_status: None, ..metrics };
+        assert!(active_limiters(&metrics).is_none());
+        assert!(limiters(&metrics).is_empty());
+    }
+}
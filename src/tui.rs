@@ -0,0 +1,359 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * Interactive tuning: live graphs next to the editable settings of a profile
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, stdout};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{enable_raw_mode, EnterAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Sparkline};
+use ratatui::{DefaultTerminal, Frame};
+use crate::{fan_ctrl, od, sensors, DeviceConfig};
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const HISTORY: usize = 240;
+const PAGE_STEPS: i64 = 10;
+
+// Where the value lives in the profile
+#[derive(Clone, Copy)]
+enum Location {
+    Value, // The line after "KEY:"
+    State(u8), // "n: [value]Mhz" in the section
+}
+
+#[derive(Clone)]
+struct Setting {
+    key: &'static str,
+    location: Location,
+    suffix: &'static str, // Unit written to the profile
+    unit: &'static str, // Unit shown
+    scale: i64, // Shown value = value / scale
+    step: i64,
+    range: (i64, i64),
+    value: i64,
+    original: i64,
+}
+
+impl Setting {
+    fn label(&self) -> String {
+        match self.location {
+            Location::Value => self.key.to_owned(),
+            Location::State(n) => format!("{} {}", self.key, n),
+        }
+    }
+
+    fn show(&self, value: i64) -> String {
+        format!("{}{}", value / self.scale, self.unit)
+    }
+}
+
+// Body of a "KEY:" section: (index of the key line, index after the last line)
+fn section(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| line.trim() == format!("{}:", key))?;
+    let end = lines[start + 1..].iter().position(|line| line.trim().is_empty())
+        .map(|i| start + 1 + i)
+        .unwrap_or(lines.len());
+    Some((start, end))
+}
+
+fn parse_number(value: &str) -> Option<i64> {
+    value.trim().trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '%').parse().ok()
+}
+
+fn read_setting(lines: &[String], key: &str, location: Location) -> Option<i64> {
+    let (start, end) = section(lines, key)?;
+    match location {
+        Location::Value => lines.get(start + 1).and_then(|line| parse_number(line)),
+        Location::State(n) => lines[start + 1..end].iter()
+            .filter_map(|line| od::parse_od_state(line))
+            .find(|(index, _, _)| *index == n)
+            .map(|(_, clock, _)| i64::from(clock)),
+    }
+}
+
+fn write_setting(lines: &mut Vec<String>, setting: &Setting) {
+    let value = match setting.location {
+        Location::Value => format!("{}{}", setting.value, setting.suffix),
+        Location::State(n) => format!("{}: {}{}", n, setting.value, setting.suffix),
+    };
+    let Some((start, end)) = section(lines, setting.key) else {
+        if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("{}:", setting.key));
+        lines.push(value);
+        return;
+    };
+    match setting.location {
+        Location::Value if end > start + 1 => lines[start + 1] = value,
+        Location::Value => lines.insert(start + 1, value),
+        Location::State(n) => {
+            match (start + 1..end).find(|i| od::parse_od_state(&lines[*i]).is_some_and(|state| state.0 == n)) {
+                Some(i) => lines[i] = value,
+                None => lines.insert(end, value),
+            }
+        },
+    }
+}
+
+fn read_hwmon(config: &DeviceConfig, name: &str) -> Option<i64> {
+    fs::read_to_string(config.hwmon_path.join(name)).ok()?.trim().parse().ok()
+}
+
+// The settings the card can change, starting from the profile (or the current card value)
+fn editable_settings(config: &DeviceConfig, lines: &[String]) -> Vec<Setting> {
+    let mut settings = Vec::new();
+    let mut add = |key, location, suffix, unit, scale, step, range: Option<(i64, i64)>, current: Option<i64>| {
+        let (Some(range), Some(current)) = (range, read_setting(lines, key, location).or(current)) else {
+            return;
+        };
+        settings.push(Setting { key, location, suffix, unit, scale, step, range, value: current, original: current });
+    };
+
+    // POWER_CAP (uW)
+    let power_cap_range = read_hwmon(config, "power1_cap_min").zip(read_hwmon(config, "power1_cap_max"));
+    add("POWER_CAP", Location::Value, "", "W", 1_000_000, 1_000_000, power_cap_range, read_hwmon(config, "power1_cap"));
+
+    if let Some(table) = od::read_od_table(&config.home_path) {
+        let range = |name: &str| table.range(name).map(|range| (range.min, range.max));
+        let current = |key: &str, index: Option<u8>| table.section(key)
+            .and_then(|section| section.entries.iter().find(|entry| entry.index == index))
+            .and_then(|entry| entry.values.first().copied());
+        if table.section("OD_SCLK_OFFSET").is_some() {
+            add("OD_SCLK_OFFSET", Location::Value, "Mhz", "Mhz", 1, 10, range("SCLK_OFFSET"), current("OD_SCLK_OFFSET", None));
+        }
+        // Min/max sections only (Vega/Polaris have one entry per state)
+        if table.section("OD_SCLK").is_some_and(|section| section.entries.len() == 2) {
+            add("OD_SCLK", Location::State(1), "Mhz", "Mhz", 1, 10, range("SCLK"), current("OD_SCLK", Some(1)));
+        }
+        if table.section("OD_MCLK").is_some_and(|section| section.entries.len() == 2) {
+            add("OD_MCLK", Location::State(1), "Mhz", "Mhz", 1, 10, range("MCLK"), current("OD_MCLK", Some(1)));
+        }
+        if table.section("OD_VDDGFX_OFFSET").is_some() {
+            add("OD_VDDGFX_OFFSET", Location::Value, "mV", "mV", 1, 5, range("VDDGFX_OFFSET"), current("OD_VDDGFX_OFFSET", None));
+        }
+    }
+
+    let fan_ctrl_settings = [
+        ("FAN_TARGET_TEMPERATURE", "fan_target_temperature", "C", 1),
+        ("FAN_MINIMUM_PWM", "fan_minimum_pwm", "%", 1),
+        ("OD_ACOUSTIC_LIMIT", "acoustic_limit_rpm_threshold", " RPM", 50),
+        ("OD_ACOUSTIC_TARGET", "acoustic_target_rpm_threshold", " RPM", 50),
+    ];
+    for (key, file, unit, step) in fan_ctrl_settings {
+        if let Some(value) = fan_ctrl::read_fan_ctrl_value(&config.home_path, file) {
+            let range = value.range.map(|(min, max)| (i64::from(min), i64::from(max)));
+            add(key, Location::Value, "", unit, 1, step, range, value.value.map(i64::from));
+        }
+    }
+    settings
+}
+
+struct App {
+    profile: String,
+    path: String,
+    original: Vec<String>, // Profile file
+    lines: Vec<String>, // Profile with the edits
+    settings: Vec<Setting>,
+    selected: ListState,
+    history: [VecDeque<u64>; 4], // SCLK, junction temperature, power, GPU busy
+    sample: sensors::Sample,
+    show_diff: bool,
+    applied: bool, // Edits were applied to the card
+    message: String,
+}
+
+impl App {
+    fn push_sample(&mut self, sample: sensors::Sample) {
+        let values = [
+            sample.sclk,
+            sample.temp_junction.map(|t| t as u64),
+            sample.power_average.or(sample.power_input).map(|p| p as u64),
+            sample.gpu_busy.map(u64::from),
+        ];
+        for (history, value) in self.history.iter_mut().zip(values) {
+            if history.len() == HISTORY {
+                history.pop_front();
+            }
+            history.push_back(value.unwrap_or(0));
+        }
+        self.sample = sample;
+    }
+
+    fn adjust(&mut self, steps: i64) {
+        let Some(setting) = self.selected.selected().and_then(|i| self.settings.get_mut(i)) else {
+            return;
+        };
+        setting.value = (setting.value + steps * setting.step).clamp(setting.range.0, setting.range.1);
+        let setting = setting.clone();
+        write_setting(&mut self.lines, &setting);
+    }
+
+    fn diff(&self) -> Vec<String> {
+        self.settings.iter()
+            .filter(|setting| setting.value != setting.original)
+            .map(|setting| format!("{}: {} -> {}", setting.label(), setting.show(setting.original), setting.show(setting.value)))
+            .collect()
+    }
+
+    fn reset_edits(&mut self) {
+        self.lines = self.original.clone();
+        for setting in self.settings.iter_mut() {
+            setting.value = setting.original;
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [body, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+    let [graphs, side] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    let graph_areas = Layout::vertical([Constraint::Ratio(1, 4); 4]).split(graphs);
+    let s = &app.sample;
+    let titles = [
+        format!("SCLK {}Mhz", s.sclk.map(|v| v.to_string()).unwrap_or_default()),
+        format!("Junction {:.0}C", s.temp_junction.unwrap_or(0.0)),
+        format!("Power {:.1}W", s.power_average.or(s.power_input).unwrap_or(0.0)),
+        format!("GPU busy {}%", s.gpu_busy.unwrap_or(0)),
+    ];
+    for ((area, history), title) in graph_areas.iter().zip(&app.history).zip(titles) {
+        let data: Vec<u64> = history.iter().copied().collect();
+        // Newest sample on the right
+        let width = usize::from(area.width.saturating_sub(2));
+        let data = &data[data.len().saturating_sub(width)..];
+        frame.render_widget(Sparkline::default().block(Block::bordered().title(title)).data(data), *area);
+    }
+
+    if app.show_diff {
+        let diff = app.diff();
+        let lines: Vec<Line> = if diff.is_empty() {
+            vec![Line::from("No changes")]
+        } else {
+            diff.into_iter().map(Line::from).collect()
+        };
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title("Diff (d to close)")), side);
+    } else {
+        let items: Vec<ListItem> = app.settings.iter().map(|setting| {
+            let modified = if setting.value != setting.original { "*" } else { " " };
+            ListItem::new(format!("{}{:<24} {:>8}  [{} - {}]", modified, setting.label(), setting.show(setting.value),
+                setting.show(setting.range.0), setting.show(setting.range.1)))
+        }).collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(format!("Profile {}", app.profile)))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, side, &mut app.selected);
+    }
+
+    let help = "up/down select  left/right adjust (pgup/pgdn x10)  d diff  a apply  r revert  s save  q quit";
+    frame.render_widget(Paragraph::new(vec![Line::from(help).dim(), Line::from(app.message.as_str())])
+        .block(Block::bordered()), status);
+}
+
+// Leaves the tui while applying (apply_profile prints its progress and may panic)
+fn apply(terminal: &mut DefaultTerminal, app: &App, lines: &[String]) -> io::Result<bool> {
+    ratatui::restore();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| crate::apply_profile(&app.profile, &app.path, lines)));
+    println!("\nPress Enter to return");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(result.is_ok())
+}
+
+fn run_app(terminal: &mut DefaultTerminal, config: &DeviceConfig, app: &mut App) -> io::Result<()> {
+    let mut last_sample = Instant::now() - REFRESH_INTERVAL;
+    loop {
+        if last_sample.elapsed() >= REFRESH_INTERVAL {
+            app.push_sample(sensors::read_sample(&config.home_path, Some(&config.hwmon_path)));
+            last_sample = Instant::now();
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+        if !event::poll(REFRESH_INTERVAL.saturating_sub(last_sample.elapsed()))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => app.selected.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => app.selected.select_next(),
+            KeyCode::Left | KeyCode::Char('h') => app.adjust(-1),
+            KeyCode::Right | KeyCode::Char('l') => app.adjust(1),
+            KeyCode::PageDown => app.adjust(-PAGE_STEPS),
+            KeyCode::PageUp => app.adjust(PAGE_STEPS),
+            KeyCode::Char('d') => app.show_diff = !app.show_diff,
+            KeyCode::Char('a') => {
+                let lines = app.lines.clone();
+                app.message = if apply(terminal, app, &lines)? {
+                    app.applied = true;
+                    String::from("Applied (not saved)")
+                } else {
+                    String::from("Failed to apply (see the output above)")
+                };
+            },
+            KeyCode::Char('r') => {
+                app.reset_edits();
+                app.message = String::from("Reverted the edits");
+                if app.applied {
+                    let lines = app.original.clone();
+                    app.applied = false;
+                    if !apply(terminal, app, &lines)? {
+                        app.message = String::from("Failed to re-apply the profile");
+                    }
+                }
+            },
+            KeyCode::Char('s') => {
+                let content = app.lines.join("\n") + "\n";
+                app.message = match fs::write(&app.path, content) {
+                    Ok(_) => {
+                        app.original = app.lines.clone();
+                        for setting in app.settings.iter_mut() {
+                            setting.original = setting.value;
+                        }
+                        format!("Saved {}", app.path)
+                    },
+                    Err(e) => format!("Failed to save {}: {}", app.path, e),
+                };
+            },
+            _ => {},
+        }
+    }
+}
+
+pub fn run(profile: &str, path: &str, config: &DeviceConfig, lines: Vec<String>) {
+    let settings = editable_settings(config, &lines);
+    let mut app = App {
+        profile: profile.to_owned(),
+        path: path.to_owned(),
+        original: lines.clone(),
+        lines,
+        selected: ListState::default().with_selected((!settings.is_empty()).then_some(0)),
+        settings,
+        history: Default::default(),
+        sample: sensors::Sample::default(),
+        show_diff: false,
+        applied: false,
+        message: String::new(),
+    };
+    let mut terminal = ratatui::init();
+    let result = run_app(&mut terminal, config, &mut app);
+    ratatui::restore();
+    result.expect("Terminal error");
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: GPL-2.0-only
+
+/*
+ * udev rule generator (apply a profile when its GPU shows up)
+ *
+ * Copyright (c) 2025 yuheho7749
+ */
+
+use std::fs;
+use std::path::PathBuf;
+use clap::Subcommand;
+use crate::systemd;
+
+// Only the card itself, not its connectors (card1-DP-1, card1-HDMI-A-1, ...)
+const CARD_MATCH: &str = "KERNEL==\"card[0-9]*\", ENV{DEVTYPE}==\"drm_minor\"";
+
+#[derive(Subcommand, Debug)]
+pub enum UdevCommands {
+    /// Print (or write) udev rules that start amdgpu-settings@[PROFILE_NAME].service when the card is added
+    Generate {
+        /// Profiles to apply (only one profile per GPU makes sense)
+        #[arg(default_values_t=[String::from("default")])]
+        profiles: Vec<String>,
+        /// Rules file to write (e.g. /etc/udev/rules.d/99-amdgpu-settings.rules)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+// Match on the unique_id attribute of the PCI device, or on its PCI slot for CARD profiles so
+// the rule still matches if the card number changes (e.g. eGPU plugged in after boot). Also returns
+// a note for the rules file when it has to fall back to the card number.
+fn device_match(profile: &str) -> (String, Option<String>) {
+    let lines = crate::read_profile_lines(&(crate::CONFIG_PROFILE_PATH.to_owned() + profile));
+    let config = crate::parse_profile_target(&lines);
+    if let Some(device_id) = config.device_id {
+        return (format!("{}, ATTRS{{unique_id}}==\"{:016x}\"", CARD_MATCH, device_id), None);
+    }
+    let card = config.card.expect("Invalid CARD #");
+    if let Some(card_path) = crate::find_card_path(&config) {
+        if let Ok(device_path) = fs::canonicalize(card_path.join("device")) {
+            let slot = device_path.file_name().unwrap().to_string_lossy().into_owned();
+            return (format!("{}, KERNELS==\"{}\"", CARD_MATCH, slot), None);
+        }
+    }
+    let note = format!("Card {} not found, matching on the card number instead of the PCI slot", card);
+    (format!("KERNEL==\"card{}\", ENV{{DEVTYPE}}==\"drm_minor\"", card), Some(note))
+}
+
+// The unit waits for the device (set --wait) so the udev worker isn't blocked like with RUN
+fn rule(profile: &str) -> String {
+    let (device_match, note) = device_match(profile);
+    let mut rule = format!("# {}{}\n", crate::CONFIG_PROFILE_PATH, profile);
+    if let Some(note) = note {
+        rule.push_str(&format!("# {}\n", note));
+    }
+    rule.push_str(&format!(
+        "ACTION==\"add\", SUBSYSTEM==\"drm\", {}, TAG+=\"systemd\", ENV{{SYSTEMD_WANTS}}+=\"{}\"\n",
+        device_match, systemd::instance_unit(profile),
+    ));
+    rule
+}
+
+fn generate(profiles: &[String], output: Option<PathBuf>) {
+    let mut rules = String::from("# Generated by amdgpu-settings\n");
+    for profile in profiles {
+        rules.push_str(&rule(profile));
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(&path, rules).expect("Failed to write udev rules");
+            println!("{}", path.display());
+            println!("Run `udevadm control --reload` to load the new rules");
+        },
+        None => print!("{}", rules),
+    }
+}
+
+pub fn run(command: UdevCommands) {
+    match command {
+        UdevCommands::Generate{profiles, output} => generate(&profiles, output),
+    }
+}
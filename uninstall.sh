@@ -2,6 +2,7 @@
 
 sudo rm /usr/local/bin/amdgpu-settings
 
-sudo rm /usr/lib/systemd/system-sleep/amdgpu-settings.resume
+sudo rm -f /usr/lib/systemd/system-sleep/amdgpu-settings
+sudo rm -f /usr/lib/systemd/system-sleep/amdgpu-settings.resume
 
 sudo rm /etc/systemd/system/amdgpu-settings.service